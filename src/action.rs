@@ -0,0 +1,211 @@
+use crate::mode::Mode;
+
+/// Something a key can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    SwitchMode,
+    Quit,
+    Search,
+    DeleteWord,
+    ToggleDescriptions,
+    ToggleFuzzy,
+    Insert,
+    Install,
+    Remove,
+    InfoDown,
+    InfoUp,
+    PageBack,
+    MoveDown,
+    MoveUp,
+    PageForward,
+    GoToStart,
+    GoToEnd,
+    ToggleSelect,
+    ClearSelection,
+    Upgrades,
+    SystemUpgrade,
+    Orphans,
+    RemoveOrphans,
+    ToggleReason,
+    Confirm,
+    Cancel,
+    Dependencies,
+    Dependents,
+    Files,
+    Pkgbuild,
+    Expand,
+    Collapse,
+    Jump,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in the help.
+    pub const ALL: [Action; 33] = [
+        Action::SwitchMode,
+        Action::Quit,
+        Action::Search,
+        Action::DeleteWord,
+        Action::ToggleDescriptions,
+        Action::ToggleFuzzy,
+        Action::Insert,
+        Action::Install,
+        Action::InfoDown,
+        Action::InfoUp,
+        Action::PageBack,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::PageForward,
+        Action::GoToStart,
+        Action::GoToEnd,
+        Action::ToggleSelect,
+        Action::ClearSelection,
+        Action::Remove,
+        Action::Upgrades,
+        Action::SystemUpgrade,
+        Action::Orphans,
+        Action::RemoveOrphans,
+        Action::ToggleReason,
+        Action::Dependencies,
+        Action::Dependents,
+        Action::Files,
+        Action::Pkgbuild,
+        Action::Confirm,
+        Action::Expand,
+        Action::Collapse,
+        Action::Jump,
+        Action::Cancel,
+    ];
+
+    /// The name of the action in the `[keys]` table of the configuration.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Action::SwitchMode => "switch-mode",
+            Action::Quit => "quit",
+            Action::Search => "search",
+            Action::DeleteWord => "delete-word",
+            Action::ToggleDescriptions => "toggle-descriptions",
+            Action::ToggleFuzzy => "toggle-fuzzy",
+            Action::Insert => "insert",
+            Action::Install => "install",
+            Action::Remove => "remove",
+            Action::InfoDown => "info-down",
+            Action::InfoUp => "info-up",
+            Action::PageBack => "page-back",
+            Action::MoveDown => "move-down",
+            Action::MoveUp => "move-up",
+            Action::PageForward => "page-forward",
+            Action::GoToStart => "go-to-start",
+            Action::GoToEnd => "go-to-end",
+            Action::ToggleSelect => "toggle-select",
+            Action::ClearSelection => "clear-selection",
+            Action::Upgrades => "upgrades",
+            Action::SystemUpgrade => "system-upgrade",
+            Action::Orphans => "orphans",
+            Action::RemoveOrphans => "remove-orphans",
+            Action::ToggleReason => "toggle-reason",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Dependencies => "dependencies",
+            Action::Dependents => "dependents",
+            Action::Files => "files",
+            Action::Pkgbuild => "pkgbuild",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Jump => "jump",
+        }
+    }
+
+    pub const fn description(&self) -> &'static str {
+        match self {
+            Action::SwitchMode => "Switch modes",
+            Action::Quit => "Exit parui",
+            Action::Search => "Search for query",
+            Action::DeleteWord => "Remove previous word",
+            Action::ToggleDescriptions => "Toggle searching descriptions",
+            Action::ToggleFuzzy => "Toggle fuzzy matching",
+            Action::Insert => "Enter insert mode",
+            Action::Install => "Install selected packages",
+            Action::Remove => "Remove selected packages",
+            Action::InfoDown => "Move info one row down",
+            Action::InfoUp => "Move info one row up",
+            Action::PageBack => "Move one page back",
+            Action::MoveDown => "Move one row down",
+            Action::MoveUp => "Move one row up",
+            Action::PageForward => "Move one page forwards",
+            Action::GoToStart => "Go to start",
+            Action::GoToEnd => "Go to end",
+            Action::ToggleSelect => "Select/deselect package",
+            Action::ClearSelection => "Clear selections",
+            Action::Upgrades => "Show/hide upgradable packages",
+            Action::SystemUpgrade => "Upgrade the whole system",
+            Action::Orphans => "Show/hide orphaned packages",
+            Action::RemoveOrphans => "Remove every orphaned package",
+            Action::ToggleReason => "Mark as explicit/as a dependency",
+            Action::Confirm => "Hand the packages over to the helper",
+            Action::Cancel => "Go back without doing anything",
+            Action::Dependencies => "Show the dependency tree",
+            Action::Dependents => "Show the installed packages depending on it",
+            Action::Files => "Show the files of the package",
+            Action::Pkgbuild => "Show the PKGBUILD of the package",
+            Action::Expand => "Expand package",
+            Action::Collapse => "Collapse package or go to parent",
+            Action::Jump => "Go to package",
+        }
+    }
+
+    /// The keys bound to the action in `mode` unless configured otherwise.
+    pub const fn default_keys(&self, mode: Mode) -> &'static [&'static str] {
+        match (self, mode) {
+            (Action::SwitchMode, Mode::Insert | Mode::Select) => &["<Escape>"],
+            (Action::Quit, Mode::Select) => &["q", "<C-c>"],
+            (Action::Quit, _) => &["<C-c>"],
+            (Action::Search, Mode::Insert) => &["<Return>"],
+            (Action::DeleteWord, Mode::Insert) => &["<C-w>", "<C-h>"],
+            (Action::ToggleDescriptions, Mode::Insert) => &["<C-d>"],
+            (Action::ToggleFuzzy, Mode::Insert) => &["<C-f>"],
+            (Action::Insert, Mode::Select) => &["i", "/"],
+            (Action::Install, Mode::Select) => &["<Return>"],
+            (Action::Remove, Mode::Select) => &["<S-R>"],
+            (Action::InfoDown, Mode::Select) => &["<C-j>", "<C-Down>"],
+            (Action::InfoUp, Mode::Select) => &["<C-k>", "<C-Up>"],
+            (Action::InfoDown, Mode::Files) => &["<Down>", "<C-j>", "<C-Down>"],
+            (Action::InfoUp, Mode::Files) => &["<Up>", "<C-k>", "<C-Up>"],
+            (Action::PageBack, Mode::Files) => &["<PgUp>"],
+            (Action::PageForward, Mode::Files) => &["<PgDn>"],
+            (Action::InfoDown, Mode::Pkgbuild) => &["j", "<Down>", "<C-j>"],
+            (Action::InfoUp, Mode::Pkgbuild) => &["k", "<Up>", "<C-k>"],
+            (Action::PageBack, Mode::Pkgbuild) => &["<PgUp>"],
+            (Action::PageForward, Mode::Pkgbuild) => &["<PgDn>", "<Space>"],
+            (Action::InfoDown, Mode::Review) => &["j", "<Down>", "<C-j>"],
+            (Action::InfoUp, Mode::Review) => &["k", "<Up>", "<C-k>"],
+            (Action::PageBack, Mode::Review) => &["<PgUp>"],
+            (Action::PageForward, Mode::Review) => &["<PgDn>", "<Space>"],
+            (Action::PageBack, Mode::Select) => &["h", "<Left>", "<PgUp>"],
+            (Action::MoveDown, Mode::Select | Mode::Tree) => &["j", "<Down>"],
+            (Action::MoveUp, Mode::Select | Mode::Tree) => &["k", "<Up>"],
+            (Action::PageForward, Mode::Select) => &["l", "<Right>", "<PgDn>"],
+            (Action::GoToStart, Mode::Select) => &["g", "<Home>"],
+            (Action::GoToEnd, Mode::Select) => &["G", "<End>"],
+            (Action::ToggleSelect, Mode::Select) => &["<Space>"],
+            (Action::ClearSelection, Mode::Select) => &["c"],
+            (Action::Upgrades, Mode::Select) => &["u"],
+            (Action::SystemUpgrade, Mode::Select) => &["<S-U>"],
+            (Action::Orphans, Mode::Select) => &["o"],
+            (Action::RemoveOrphans, Mode::Select) => &["<S-O>"],
+            (Action::ToggleReason, Mode::Select) => &["e"],
+            (Action::Confirm, Mode::Confirm | Mode::Review) => &["<Return>", "y"],
+            (Action::Cancel, Mode::Confirm | Mode::Review) => &["<Escape>", "n", "q"],
+            (Action::Cancel, Mode::Tree) => &["<Escape>", "q"],
+            (Action::Cancel, Mode::Files) => &["<Escape>"],
+            (Action::Cancel, Mode::Pkgbuild) => &["<Escape>", "q"],
+            (Action::Dependencies, Mode::Select) => &["d"],
+            (Action::Dependents, Mode::Select) => &["<S-D>"],
+            (Action::Files, Mode::Select) => &["f"],
+            (Action::Pkgbuild, Mode::Select) => &["p"],
+            (Action::Expand, Mode::Tree) => &["l", "<Right>", "<Space>"],
+            (Action::Collapse, Mode::Tree) => &["h", "<Left>"],
+            (Action::Jump, Mode::Tree) => &["<Return>"],
+            _ => &[],
+        }
+    }
+}
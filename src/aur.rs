@@ -0,0 +1,240 @@
+use std::{io::BufReader, sync::LazyLock, time::Duration};
+
+use flate2::bufread::GzDecoder;
+use serde::Deserialize;
+use ureq::Agent;
+
+pub const AUR_URL: &str = "https://aur.archlinux.org";
+
+/// How long a request to the AUR may take before giving up on it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long downloading the metadata dump may take, as it is several megabytes.
+const DUMP_TIMEOUT: Duration = Duration::from_secs(60);
+
+static AGENT: LazyLock<Agent> = LazyLock::new(|| {
+    Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .into()
+});
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    results: Vec<AurInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AurInfo {
+    pub name: String,
+    pub package_base: String,
+    pub version: String,
+    pub description: Option<String>,
+    #[serde(rename = "URL")]
+    pub url: Option<String>,
+    #[serde(rename = "URLPath")]
+    pub url_path: Option<String>,
+    pub num_votes: u64,
+    pub popularity: f64,
+    pub out_of_date: Option<u64>,
+    pub maintainer: Option<String>,
+    pub submitter: Option<String>,
+    pub first_submitted: u64,
+    pub last_modified: u64,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub license: Vec<String>,
+    #[serde(default)]
+    pub provides: Vec<String>,
+    #[serde(default)]
+    pub depends: Vec<String>,
+    #[serde(default)]
+    pub make_depends: Vec<String>,
+    #[serde(default)]
+    pub check_depends: Vec<String>,
+    #[serde(default)]
+    pub opt_depends: Vec<String>,
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+    #[serde(default)]
+    pub replaces: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub co_maintainers: Vec<String>,
+}
+
+/// An entry of the AUR metadata dump (`packages-meta-v1.json.gz`).
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AurPackage {
+    pub name: Box<str>,
+    pub version: Box<str>,
+    pub description: Option<Box<str>>,
+    pub maintainer: Option<Box<str>>,
+    pub num_votes: u64,
+}
+
+/// Downloads the metadata of every package in the AUR at `base`.
+pub fn packages(base: &str) -> Option<Vec<AurPackage>> {
+    let response = AGENT
+        .get(format!("{base}/packages-meta-v1.json.gz"))
+        .config()
+        .timeout_global(Some(DUMP_TIMEOUT))
+        .build()
+        .call()
+        .ok()?;
+
+    let reader = BufReader::new(response.into_body().into_reader());
+    serde_json::from_reader(BufReader::new(GzDecoder::new(reader))).ok()
+}
+
+/// Queries the AUR RPC v5 `info` endpoint at `base` for `name`.
+///
+/// Returns `None` if the request fails or if the AUR does not know the package.
+pub fn info(base: &str, name: &str) -> Option<AurInfo> {
+    let response = AGENT
+        .get(format!("{base}/rpc/v5/info"))
+        .query("arg[]", name)
+        .call()
+        .ok()?;
+
+    let response: Response = serde_json::from_reader(response.into_body().into_reader()).ok()?;
+    response.results.into_iter().find(|info| info.name == name)
+}
+
+/// Fetches the PKGBUILD of `base` from the cgit interface of the AUR at `base_url`.
+pub fn pkgbuild(base_url: &str, base: &str) -> Option<String> {
+    AGENT
+        .get(format!("{base_url}/cgit/aur.git/plain/PKGBUILD"))
+        .query("h", base)
+        .call()
+        .ok()?
+        .into_body()
+        .read_to_string()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+
+    /// Answers a single request on a local port with `status` and `body`, returning the base URL
+    /// and a handle to the request line that was received.
+    fn serve(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap() > 2 {
+                header.clear();
+            }
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            request
+        });
+
+        (base, handle)
+    }
+
+    const FOUND: &str = r#"{
+        "version": 5,
+        "type": "multiinfo",
+        "resultcount": 1,
+        "results": [{
+            "Name": "parui",
+            "PackageBase": "parui",
+            "Version": "1.0.19-1",
+            "Description": "Simple TUI frontend for paru or yay.",
+            "URL": "https://github.com/Vonr/parui",
+            "URLPath": "/cgit/aur.git/snapshot/parui.tar.gz",
+            "NumVotes": 12,
+            "Popularity": 0.5,
+            "OutOfDate": null,
+            "Maintainer": "vonr",
+            "FirstSubmitted": 1650000000,
+            "LastModified": 1700000000,
+            "Depends": ["glibc"],
+            "MakeDepends": ["cargo"]
+        }]
+    }"#;
+
+    #[test]
+    fn info_found() {
+        let (base, request) = serve("200 OK", FOUND);
+        let info = info(&base, "parui").unwrap();
+        let request = request.join().unwrap();
+
+        assert!(request.starts_with("GET /rpc/v5/info?"));
+        assert!(request.contains("=parui "));
+        assert_eq!(info.package_base, "parui");
+        assert_eq!(info.version, "1.0.19-1");
+        assert_eq!(info.num_votes, 12);
+        assert_eq!(info.maintainer.as_deref(), Some("vonr"));
+        assert_eq!(info.depends, ["glibc"]);
+        assert_eq!(info.make_depends, ["cargo"]);
+        assert!(info.opt_depends.is_empty());
+    }
+
+    #[test]
+    fn info_not_found() {
+        let (base, _) = serve(
+            "200 OK",
+            r#"{"version":5,"type":"multiinfo","resultcount":0,"results":[]}"#,
+        );
+        assert!(info(&base, "parui").is_none());
+    }
+
+    #[test]
+    fn info_other_package() {
+        let (base, _) = serve("200 OK", FOUND);
+        assert!(info(&base, "paru").is_none());
+    }
+
+    #[test]
+    fn info_server_error() {
+        let (base, _) = serve("503 Service Unavailable", "");
+        assert!(info(&base, "parui").is_none());
+    }
+
+    #[test]
+    fn info_malformed() {
+        let (base, _) = serve("200 OK", "<html>Bad Gateway</html>");
+        assert!(info(&base, "parui").is_none());
+    }
+
+    #[test]
+    fn pkgbuild_found() {
+        let (base, request) = serve("200 OK", "pkgname=parui\npkgver=1.0.19\n");
+        let text = pkgbuild(&base, "parui").unwrap();
+        let request = request.join().unwrap();
+
+        assert!(request.starts_with("GET /cgit/aur.git/plain/PKGBUILD?h=parui "));
+        assert_eq!(text, "pkgname=parui\npkgver=1.0.19\n");
+    }
+
+    #[test]
+    fn pkgbuild_not_found() {
+        let (base, _) = serve("404 Not Found", "Invalid branch: parui");
+        assert!(pkgbuild(&base, "parui").is_none());
+    }
+}
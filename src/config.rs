@@ -1,26 +1,57 @@
-use std::{env::Args, process::exit};
+use std::{
+    env::{self, Args},
+    path::PathBuf,
+    process::exit,
+};
 
-use self::help::print_help;
+pub use self::keys::Keymap;
+use self::{
+    file::{File, Preset},
+    help::print_help,
+};
+use crate::{interface::Column, theme::Theme};
 
+mod file;
 mod help;
+mod keys;
 
 pub struct Config {
     pub query: Option<String>,
     pub command: String,
+    pub flags: Vec<String>,
+    pub aur: bool,
+    /// Whether to run the helper as a child and return to parui once it exits, instead of
+    /// replacing parui with it.
+    pub keep_open: bool,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// The percentage of the width taken by the results list.
+    pub list_ratio: u16,
+    pub columns: Vec<Column>,
 }
 
 impl Config {
     pub fn new(args: Args) -> Self {
         let mut query: Option<String> = None;
         let mut command = None;
+        let mut aur = None;
+        let mut keep_open = None;
+        let mut path = None;
+        let mut help = false;
 
         for arg in args.skip(1) {
             match arg.as_str() {
-                "-h" | "--help" => print_help(),
+                "-h" | "--help" => help = true,
+                "--aur" => aur = Some(true),
+                "--no-aur" => aur = Some(false),
+                "--keep-open" => keep_open = Some(true),
+                "--no-keep-open" => keep_open = Some(false),
                 #[allow(clippy::option_if_let_else)]
                 _ => {
                     if let Some(stripped) = arg.strip_prefix("-p=") {
                         command = Some(stripped.to_string());
+                    } else if let Some(stripped) = arg.strip_prefix("-c=") {
+                        path = Some(PathBuf::from(stripped));
                     } else if let Some(q) = query {
                         query = Some(q + " " + &arg);
                     } else {
@@ -30,7 +61,41 @@ impl Config {
             }
         }
 
-        let command = command.unwrap_or_else(|| String::from("paru"));
+        let explicit = path.is_some();
+        let path = path.or_else(File::default_path).unwrap_or_default();
+        let file = match std::fs::read_to_string(&path) {
+            Ok(contents) => File::parse(&contents).unwrap_or_else(|err| {
+                eprintln!("parui: {}: {err}", path.display());
+                exit(1);
+            }),
+            Err(err) if explicit || err.kind() != std::io::ErrorKind::NotFound => {
+                eprintln!("parui: {}: {err}", path.display());
+                exit(1);
+            }
+            Err(_) => File::default(),
+        };
+
+        let keymap = Keymap::new(&file.keys).unwrap_or_else(|err| {
+            eprintln!("parui: {}: {err}", path.display());
+            exit(1);
+        });
+
+        if help {
+            print_help(&keymap);
+        }
+
+        let list_ratio = file.layout.list.unwrap_or(50);
+        if !(20..=80).contains(&list_ratio) {
+            eprintln!(
+                "parui: {}: layout.list must be between 20 and 80, got {list_ratio}",
+                path.display()
+            );
+            exit(1);
+        }
+
+        let command = command
+            .or(file.command)
+            .unwrap_or_else(|| String::from("paru"));
 
         if let Err(err) = std::process::Command::new(&command).arg("--help").output() {
             match err.kind() {
@@ -44,6 +109,30 @@ impl Config {
             exit(1);
         }
 
-        Self { query, command }
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let preset = file.theme.preset.unwrap_or(if no_color {
+            Preset::Monochrome
+        } else {
+            Preset::Default
+        });
+        let mut theme = preset.theme();
+        file.theme.apply(&mut theme);
+
+        let aur = aur.or(file.aur).unwrap_or(true) && command != "pacman";
+
+        Self {
+            query,
+            command,
+            flags: file.flags,
+            aur,
+            keep_open: keep_open.or(file.keep_open).unwrap_or(false),
+            theme,
+            keymap,
+            list_ratio,
+            columns: file
+                .layout
+                .columns
+                .unwrap_or_else(|| Column::DEFAULT.to_vec()),
+        }
     }
 }
@@ -0,0 +1,273 @@
+use std::{collections::HashMap, env, path::PathBuf, str::FromStr};
+
+use serde::{de::Error, Deserialize, Deserializer};
+use tui::style::{Color, Modifier, Style};
+
+use crate::{interface::Column, theme::Theme};
+
+/// The contents of `config.toml`, every field of which is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct File {
+    /// The helper used to search for and install packages.
+    pub command: Option<String>,
+    /// Flags passed to the helper when installing or upgrading packages, never when removing them.
+    pub flags: Vec<String>,
+    /// Whether to list packages from the AUR.
+    pub aur: Option<bool>,
+    /// Whether to return to parui once the helper exits.
+    pub keep_open: Option<bool>,
+    pub theme: ThemeFile,
+    /// Keys bound to each action in each mode, replacing its default keys in that mode.
+    pub keys: HashMap<String, HashMap<String, Vec<String>>>,
+    pub layout: Layout,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    Default,
+    HighContrast,
+    Monochrome,
+}
+
+impl Preset {
+    pub const fn theme(self) -> Theme {
+        match self {
+            Preset::Default => Theme::DEFAULT,
+            Preset::HighContrast => Theme::HIGH_CONTRAST,
+            Preset::Monochrome => Theme::MONOCHROME,
+        }
+    }
+}
+
+/// A preset along with styles patched onto the elements of its theme.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThemeFile {
+    pub preset: Option<Preset>,
+    #[serde(deserialize_with = "style")]
+    index: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    installed: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    installed_current: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    outdated: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    outdated_current: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    uninstalled: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    uninstalled_current: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    matched: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    selected_marker: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    dependency_marker: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    repo: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    version: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    installed_version: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    description: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    active: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    inactive: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    insert: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    select: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    confirm: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    tree: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    files: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    pkgbuild: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    review: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    info_key: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    install_hint: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    remove_hint: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    loading: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    popup: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    popup_title: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    pkgbuild_comment: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    pkgbuild_string: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    pkgbuild_variable: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    pkgbuild_keyword: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    diff_added: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    diff_removed: Option<Style>,
+    #[serde(deserialize_with = "style")]
+    diff_hunk: Option<Style>,
+}
+
+impl ThemeFile {
+    /// Patches the configured styles onto `theme`.
+    pub fn apply(&self, theme: &mut Theme) {
+        macro_rules! patch {
+            ($($element:ident),*) => {
+                $(
+                    if let Some(style) = self.$element {
+                        theme.$element = theme.$element.patch(style);
+                    }
+                )*
+            };
+        }
+
+        patch!(
+            index,
+            installed,
+            installed_current,
+            outdated,
+            outdated_current,
+            uninstalled,
+            uninstalled_current,
+            matched,
+            selected_marker,
+            dependency_marker,
+            repo,
+            version,
+            installed_version,
+            description,
+            active,
+            inactive,
+            insert,
+            select,
+            confirm,
+            tree,
+            files,
+            pkgbuild,
+            review,
+            info_key,
+            install_hint,
+            remove_hint,
+            loading,
+            popup,
+            popup_title,
+            pkgbuild_comment,
+            pkgbuild_string,
+            pkgbuild_variable,
+            pkgbuild_keyword,
+            diff_added,
+            diff_removed,
+            diff_hunk
+        );
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Layout {
+    /// The percentage of the width taken by the results list.
+    pub list: Option<u16>,
+    /// The columns shown after the name of each result.
+    pub columns: Option<Vec<Column>>,
+}
+
+impl File {
+    /// Returns `$XDG_CONFIG_HOME/parui/config.toml`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".config")))?;
+
+        Some(config_home.join("parui").join("config.toml"))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+}
+
+/// Parses styles written either as a colour like `red`, `light-blue`, `#ff8800` or `208`, or
+/// as a table like `{ fg = "yellow", bg = "red", bold = true }`.
+fn style<'de, D>(deserializer: D) -> Result<Option<Style>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged, deny_unknown_fields)]
+    enum Raw {
+        Color(String),
+        Table {
+            fg: Option<String>,
+            bg: Option<String>,
+            #[serde(default)]
+            bold: bool,
+            #[serde(default)]
+            dim: bool,
+            #[serde(default)]
+            italic: bool,
+            #[serde(default)]
+            underlined: bool,
+            #[serde(default)]
+            reversed: bool,
+        },
+    }
+
+    let color = |color: &str| {
+        Color::from_str(color).map_err(|_| D::Error::custom(format!("invalid colour `{color}`")))
+    };
+
+    let raw = Raw::deserialize(deserializer).map_err(|_| {
+        D::Error::custom(
+            "expected a colour or a table of fg, bg, bold, dim, italic, underlined and reversed",
+        )
+    })?;
+
+    let style = match raw {
+        Raw::Color(fg) => Style::default().fg(color(&fg)?),
+        Raw::Table {
+            fg,
+            bg,
+            bold,
+            dim,
+            italic,
+            underlined,
+            reversed,
+        } => {
+            let mut style = Style::default();
+            if let Some(fg) = fg {
+                style = style.fg(color(&fg)?);
+            }
+            if let Some(bg) = bg {
+                style = style.bg(color(&bg)?);
+            }
+
+            for (enabled, modifier) in [
+                (bold, Modifier::BOLD),
+                (dim, Modifier::DIM),
+                (italic, Modifier::ITALIC),
+                (underlined, Modifier::UNDERLINED),
+                (reversed, Modifier::REVERSED),
+            ] {
+                if enabled {
+                    style = style.add_modifier(modifier);
+                }
+            }
+            style
+        }
+    };
+
+    Ok(Some(style))
+}
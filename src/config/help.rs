@@ -1,6 +1,9 @@
 use std::process::exit;
 
-pub fn print_help() {
+use super::Keymap;
+use crate::mode::Mode;
+
+pub fn print_help(keymap: &Keymap) {
     println!(
         r#"Usage: parui [OPTION]... QUERY
 Search for QUERY in the Arch User Repository.
@@ -11,48 +14,53 @@ Options:
         Selects program used to search AUR
         Not guaranteed to work well
         Default: paru
+    -c=<FILE>
+        Reads configuration from FILE
+        Default: $XDG_CONFIG_HOME/parui/config.toml
+    --aur, --no-aur
+        Enables or disables listing packages from the AUR
+    --keep-open, --no-keep-open
+        Returns to parui once the helper exits instead of exiting
     -h
         Print this help and exit
-Keybinds:
-    Both:
-       <Escape>
-           Switch Modes
-       <C-c>
-           Exit parui
-   Insert:
-       <Return>
-           Search for query
-       <C-w>
-           Remove previous word
-   Select:
-       i, /
-           Enter insert mode
-       <Return>
-           Install selected packages
-       <C-j>, <C-Down>
-           Move info one row down
-       <C-k>, <C-Up>
-           Move info one row up
-       h, <Left>, <PgUp>
-           Move one page back
-       j, <Down>
-           Move one row down
-       k, <Up>
-           Move one row up
-       l, <Right>, <PgDn>
-           Move one page forwards
-       g, <Home>
-           Go to start
-       G, <End>
-           Go to end
-       <Space>
-           Select/deselect package
-       c
-           Clear selections
-       <S-R>
-           Remove selected packages
-       q
-           Exit parui"#
+Queries:
+    Terms separated by spaces must all match
+    a | b, a OR b
+        Match either term
+    !a, NOT a
+        Exclude matches of a term
+    ( ... )
+        Group terms, parentheses must be separated by spaces
+    "a b", "std::"
+        Match a term containing spaces or colons, quoted terms are never fields
+    name:, desc:, maint:
+        Match only the name, description or maintainer
+    repo:<REPO>
+        Match packages from a repository, e.g. repo:extra or repo:aur
+    installed:yes, installed:no
+        Match installed or uninstalled packages
+    upgradable:yes, upgradable:no
+        Match installed packages with or without a newer version available
+    votes:<N>, votes:>N, votes:>=N, votes:<N, votes:<=N
+        Match AUR packages by their number of votes
+    owns:<PATH>, file:<NAME>
+        List the packages owning a path or a file name, e.g. owns:/usr/bin/rg or file:rg
+        Must start the query, the rest of which is the path or file name
+        Repository packages are found once pacman -Fy downloaded the files databases
+Keybinds:"#
     );
+
+    for mode in Mode::ALL {
+        println!("    {}:", mode.name());
+        for (action, keys) in keymap.bindings(mode) {
+            if keys.is_empty() {
+                continue;
+            }
+
+            println!("       {}", keys.join(", "));
+            println!("           {}", action.description());
+        }
+    }
+
     exit(0);
 }
@@ -0,0 +1,439 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{action::Action, mode::Mode};
+
+/// A key along with the modifiers held while pressing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already accounted for by the case of characters.
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        Self { code, modifiers }
+    }
+
+    /// Parses keys written like `j`, `<Space>`, `<C-w>` or `<S-R>`.
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut rest = key
+            .strip_prefix('<')
+            .and_then(|key| key.strip_suffix('>'))
+            .unwrap_or(key);
+
+        let mut modifiers = KeyModifiers::NONE;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            modifiers |= match rest.as_bytes()[0] {
+                b'C' => KeyModifiers::CONTROL,
+                b'S' => KeyModifiers::SHIFT,
+                b'A' | b'M' => KeyModifiers::ALT,
+                _ => return None,
+            };
+            rest = &rest[2..];
+        }
+
+        let code = match rest {
+            "Return" | "Enter" | "CR" => KeyCode::Enter,
+            "Escape" | "Esc" => KeyCode::Esc,
+            "Space" => KeyCode::Char(' '),
+            "Tab" => KeyCode::Tab,
+            "Backspace" | "BS" => KeyCode::Backspace,
+            "Delete" | "Del" => KeyCode::Delete,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PgUp" | "PageUp" => KeyCode::PageUp,
+            "PgDn" | "PageDown" => KeyCode::PageDown,
+            _ => {
+                if let Some(n) = rest.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                    KeyCode::F(n)
+                } else {
+                    let mut chars = rest.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                            KeyCode::Char(c.to_ascii_uppercase())
+                        }
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return None,
+                    }
+                }
+            }
+        };
+
+        Some(Self::new(code, modifiers))
+    }
+
+    /// Whether the key types a character, which it could no longer do once bound.
+    fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl Display for Key {
+    /// Writes the key the way it is shown in hints, like `ENTER`, `Shift-R` or `Ctrl-C`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+
+        match self.code {
+            KeyCode::Enter => write!(f, "ENTER"),
+            KeyCode::Esc => write!(f, "ESC"),
+            KeyCode::Char(' ') => write!(f, "SPACE"),
+            KeyCode::Char(c) if c.is_ascii_uppercase() => write!(f, "Shift-{c}"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{c}"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Tab => write!(f, "TAB"),
+            KeyCode::Backspace => write!(f, "BACKSPACE"),
+            KeyCode::Delete => write!(f, "DELETE"),
+            KeyCode::Up => write!(f, "UP"),
+            KeyCode::Down => write!(f, "DOWN"),
+            KeyCode::Left => write!(f, "LEFT"),
+            KeyCode::Right => write!(f, "RIGHT"),
+            KeyCode::Home => write!(f, "HOME"),
+            KeyCode::End => write!(f, "END"),
+            KeyCode::PageUp => write!(f, "PAGE UP"),
+            KeyCode::PageDown => write!(f, "PAGE DOWN"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    UnknownMode(String),
+    UnknownAction(String, String),
+    /// The action does nothing in the mode.
+    Unavailable(String, String),
+    InvalidKey(String, String),
+    /// A key that would no longer be typed in a mode where text is typed.
+    TextKey(String, String),
+    Conflict(String, String),
+}
+
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeymapError::UnknownMode(mode) => write!(f, "unknown mode `{mode}` in [keys]"),
+            KeymapError::UnknownAction(mode, action) => {
+                write!(f, "unknown action `{action}` in [keys.{mode}]")
+            }
+            KeymapError::Unavailable(mode, action) => {
+                write!(f, "action `{action}` cannot be bound in [keys.{mode}]")
+            }
+            KeymapError::InvalidKey(mode, key) => write!(f, "invalid key `{key}` in [keys.{mode}]"),
+            KeymapError::TextKey(mode, key) => write!(
+                f,
+                "`{key}` could no longer be typed if it was bound in [keys.{mode}]"
+            ),
+            KeymapError::Conflict(mode, key) => {
+                write!(
+                    f,
+                    "`{key}` is bound to more than one action in [keys.{mode}]"
+                )
+            }
+        }
+    }
+}
+
+/// The actions bound to keys in each mode.
+pub struct Keymap {
+    /// The keys bound to each action as they were written, in the order they are listed in the
+    /// help.
+    bindings: Vec<(Mode, Action, Vec<String>)>,
+    actions: HashMap<(Mode, Key), Action>,
+}
+
+impl Keymap {
+    /// Creates the keymap from the `[keys]` table of the configuration, which has a table for
+    /// each mode where binding an action replaces its default keys in that mode.
+    pub fn new(keys: &HashMap<String, HashMap<String, Vec<String>>>) -> Result<Self, KeymapError> {
+        let mut configured = HashMap::new();
+        for (mode_name, actions) in keys {
+            let mode = Mode::ALL
+                .into_iter()
+                .find(|mode| mode.name().to_ascii_lowercase() == *mode_name)
+                .ok_or_else(|| KeymapError::UnknownMode(mode_name.clone()))?;
+
+            for (action_name, bound) in actions {
+                let action = Action::ALL
+                    .into_iter()
+                    .find(|action| action.name() == action_name)
+                    .ok_or_else(|| {
+                        KeymapError::UnknownAction(mode_name.clone(), action_name.clone())
+                    })?;
+                if action.default_keys(mode).is_empty() {
+                    return Err(KeymapError::Unavailable(
+                        mode_name.clone(),
+                        action_name.clone(),
+                    ));
+                }
+
+                configured.insert((mode, action), bound);
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for mode in Mode::ALL {
+            for action in Action::ALL {
+                let defaults = action.default_keys(mode);
+                if defaults.is_empty() {
+                    continue;
+                }
+
+                let keys = match configured.get(&(mode, action)) {
+                    Some(&keys) => keys.clone(),
+                    None => defaults.iter().map(|&key| key.to_owned()).collect(),
+                };
+                bindings.push((mode, action, keys));
+            }
+        }
+
+        // Keys bound in the configuration take precedence over the default keys of other
+        // actions, so they are inserted last.
+        let mut actions = HashMap::new();
+        for is_configured in [false, true] {
+            for (mode, action, bound) in &bindings {
+                if configured.contains_key(&(*mode, *action)) != is_configured {
+                    continue;
+                }
+
+                let mode_name = || mode.name().to_ascii_lowercase();
+                for key in bound {
+                    let parsed = Key::parse(key)
+                        .ok_or_else(|| KeymapError::InvalidKey(mode_name(), key.clone()))?;
+                    if is_configured
+                        && matches!(mode, Mode::Insert | Mode::Files)
+                        && parsed.is_text()
+                    {
+                        return Err(KeymapError::TextKey(mode_name(), key.clone()));
+                    }
+
+                    let previous = actions.insert((*mode, parsed), *action);
+                    if is_configured
+                        && previous.is_some_and(|previous| {
+                            previous != *action && configured.contains_key(&(*mode, previous))
+                        })
+                    {
+                        return Err(KeymapError::Conflict(mode_name(), key.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(Self { bindings, actions })
+    }
+
+    pub fn get(&self, mode: Mode, event: KeyEvent) -> Option<Action> {
+        self.actions.get(&(mode, Key::from(event))).copied()
+    }
+
+    /// Describes the first key bound to each action in `mode`, like `ENTER to continue, ESC to
+    /// cancel`, leaving out actions without keys.
+    pub fn hint(&self, mode: Mode, actions: &[(Action, &str)]) -> Option<String> {
+        let parts: Vec<_> = actions
+            .iter()
+            .filter_map(|(action, description)| {
+                let (_, keys) = self.bindings(mode).find(|(bound, _)| bound == action)?;
+                let key = Key::parse(keys.first()?)?;
+                Some(format!("{key} to {description}"))
+            })
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Returns the keys bound to each action in `mode`, in the order they are listed in the help.
+    pub fn bindings(&self, mode: Mode) -> impl Iterator<Item = (Action, Vec<&str>)> {
+        self.bindings
+            .iter()
+            .filter(move |(bound_mode, ..)| *bound_mode == mode)
+            .map(move |(_, action, keys)| {
+                let keys = keys
+                    .iter()
+                    .filter(|key| {
+                        Key::parse(key).and_then(|key| self.actions.get(&(mode, key)))
+                            == Some(action)
+                    })
+                    .map(String::as_str)
+                    .collect();
+                (*action, keys)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap_with(mode: &str, action: &str, keys: &[&str]) -> Result<Keymap, KeymapError> {
+        let actions = HashMap::from([(
+            action.to_owned(),
+            keys.iter().map(|&key| key.to_owned()).collect(),
+        )]);
+        Keymap::new(&HashMap::from([(mode.to_owned(), actions)]))
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parse() {
+        let key = |code, modifiers| Some(Key::new(code, modifiers));
+        assert_eq!(Key::parse("j"), key(KeyCode::Char('j'), KeyModifiers::NONE));
+        assert_eq!(
+            Key::parse("<C-w>"),
+            key(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Key::parse("<S-r>"),
+            key(KeyCode::Char('R'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Key::parse("<Space>"),
+            key(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(Key::parse("<Esc>"), key(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(Key::parse("F5"), key(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(Key::parse("<X-a>"), None);
+        assert_eq!(Key::parse("<Spaces>"), None);
+    }
+
+    #[test]
+    fn defaults() {
+        let keymap = Keymap::new(&HashMap::new()).unwrap();
+        let q = press(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(keymap.get(Mode::Select, q), Some(Action::Quit));
+        assert_eq!(keymap.get(Mode::Insert, q), None);
+        assert_eq!(keymap.get(Mode::Files, q), None);
+    }
+
+    #[test]
+    fn text_keys_are_kept_in_text_modes() {
+        assert!(matches!(
+            keymap_with("insert", "quit", &["q"]),
+            Err(KeymapError::TextKey(..))
+        ));
+        assert!(matches!(
+            keymap_with("files", "cancel", &["<Space>"]),
+            Err(KeymapError::TextKey(..))
+        ));
+
+        let keymap = keymap_with("insert", "quit", &["<C-q>"]).unwrap();
+        let q = press(KeyCode::Char('q'), KeyModifiers::NONE);
+        let ctrl_q = press(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.get(Mode::Insert, ctrl_q), Some(Action::Quit));
+        assert_eq!(keymap.get(Mode::Insert, q), None);
+        assert_eq!(keymap.get(Mode::Files, q), None);
+    }
+
+    #[test]
+    fn bindings_replace_defaults_in_their_mode() {
+        let keymap = keymap_with("confirm", "cancel", &["x"]).unwrap();
+        let x = press(KeyCode::Char('x'), KeyModifiers::NONE);
+        let escape = press(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(keymap.get(Mode::Confirm, x), Some(Action::Cancel));
+        assert_eq!(keymap.get(Mode::Confirm, escape), None);
+        for mode in [Mode::Tree, Mode::Files, Mode::Pkgbuild, Mode::Review] {
+            assert_eq!(keymap.get(mode, escape), Some(Action::Cancel));
+            assert_eq!(keymap.get(mode, x), None);
+        }
+
+        let (_, keys) = keymap
+            .bindings(Mode::Confirm)
+            .find(|(action, _)| *action == Action::Cancel)
+            .unwrap();
+        assert_eq!(keys, ["x"]);
+    }
+
+    #[test]
+    fn hints() {
+        let keymap = Keymap::new(&HashMap::new()).unwrap();
+        assert_eq!(
+            keymap
+                .hint(
+                    Mode::Confirm,
+                    &[(Action::Confirm, "continue"), (Action::Cancel, "cancel")]
+                )
+                .as_deref(),
+            Some("ENTER to continue, ESC to cancel")
+        );
+        assert_eq!(
+            keymap
+                .hint(Mode::Select, &[(Action::Remove, "uninstall")])
+                .as_deref(),
+            Some("Shift-R to uninstall")
+        );
+
+        let keymap = keymap_with("confirm", "cancel", &["<C-x>"]).unwrap();
+        assert_eq!(
+            keymap
+                .hint(Mode::Confirm, &[(Action::Cancel, "cancel")])
+                .as_deref(),
+            Some("Ctrl-X to cancel")
+        );
+
+        let keymap = keymap_with("select", "remove", &[]).unwrap();
+        assert_eq!(
+            keymap.hint(Mode::Select, &[(Action::Remove, "remove")]),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(matches!(
+            keymap_with("normal", "quit", &["q"]),
+            Err(KeymapError::UnknownMode(..))
+        ));
+        assert!(matches!(
+            keymap_with("select", "exit", &["q"]),
+            Err(KeymapError::UnknownAction(..))
+        ));
+        assert!(matches!(
+            keymap_with("insert", "confirm", &["<Return>"]),
+            Err(KeymapError::Unavailable(..))
+        ));
+        assert!(matches!(
+            keymap_with("select", "quit", &["<Q-q>"]),
+            Err(KeymapError::InvalidKey(..))
+        ));
+
+        let actions = HashMap::from([
+            ("quit".to_owned(), vec!["x".to_owned()]),
+            ("confirm".to_owned(), vec!["x".to_owned()]),
+        ]);
+        assert!(matches!(
+            Keymap::new(&HashMap::from([("confirm".to_owned(), actions)])),
+            Err(KeymapError::Conflict(..))
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read},
+    path::Path,
+};
+
+use flate2::bufread::GzDecoder;
+use ruzstd::decoding::StreamingDecoder;
+
+pub use self::{
+    local::{read_local_db, read_local_files, InstallReason, LocalPackage},
+    sync::{read_sync_dbs, read_sync_files, visit_sync_files, SyncPackage},
+};
+
+mod local;
+mod sync;
+
+/// Iterates over the `%KEY%` sections of a pacman `desc` file.
+///
+/// Sections are separated by blank lines, so the value of each section is every line after the
+/// key up to the next blank line.
+pub fn fields(desc: &str) -> impl Iterator<Item = (&str, &str)> {
+    desc.split("\n\n").filter_map(|section| {
+        let section = section.trim_matches('\n');
+        let (key, value) = section.split_once('\n').unwrap_or((section, ""));
+        let key = key.strip_prefix('%')?.strip_suffix('%')?;
+
+        Some((key, value))
+    })
+}
+
+/// Strips the version constraint or optional dependency description from a dependency.
+pub fn dep_name(dep: &str) -> &str {
+    dep.split(['<', '>', '=', ':']).next().unwrap_or(dep).trim()
+}
+
+pub fn list(value: &str) -> Box<[Box<str>]> {
+    value.lines().map(Box::from).collect()
+}
+
+/// Iterates over the paths in the `%FILES%` section of a pacman `files` file, which are relative
+/// to the root.
+pub fn file_paths(contents: &str) -> impl Iterator<Item = &str> {
+    fields(contents)
+        .find(|&(key, _)| key == "FILES")
+        .map(|(_, value)| value)
+        .unwrap_or_default()
+        .lines()
+}
+
+/// Reads the `%FILES%` section of a pacman `files` file, with every path made absolute.
+fn files(contents: &str) -> Box<[Box<str>]> {
+    file_paths(contents)
+        .map(|path| format!("/{path}").into())
+        .collect()
+}
+
+/// Opens a (possibly compressed) tar archive, detecting the compression by its magic bytes.
+pub fn open_archive(path: &Path) -> io::Result<tar::Archive<Box<dyn Read>>> {
+    const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+    const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 4];
+    let len = file.read(&mut magic)?;
+
+    // Put the magic bytes back in front of the rest of the file.
+    let reader = Cursor::new(magic)
+        .take(len as u64)
+        .chain(BufReader::new(file));
+
+    let reader: Box<dyn Read> = if magic[..len].starts_with(GZIP_MAGIC) {
+        Box::new(GzDecoder::new(BufReader::new(reader)))
+    } else if magic[..len].starts_with(ZSTD_MAGIC) {
+        Box::new(StreamingDecoder::new(reader).map_err(io::Error::other)?)
+    } else {
+        Box::new(reader)
+    };
+
+    Ok(tar::Archive::new(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Write};
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn desc_fields() {
+        let desc = "%NAME%\nripgrep\n\n%DEPENDS%\ngcc-libs\nglibc\n\n%EMPTY%\n\nstray\n";
+        assert_eq!(
+            fields(desc).collect::<Vec<_>>(),
+            [
+                ("NAME", "ripgrep"),
+                ("DEPENDS", "gcc-libs\nglibc"),
+                ("EMPTY", "")
+            ]
+        );
+    }
+
+    #[test]
+    fn file_lists() {
+        let contents = "%FILES%\nusr/\nusr/bin/\nusr/bin/rg\n\n%BACKUP%\netc/rg.conf\n";
+        assert_eq!(
+            file_paths(contents).collect::<Vec<_>>(),
+            ["usr/", "usr/bin/", "usr/bin/rg"]
+        );
+        assert_eq!(files(contents)[2].as_ref(), "/usr/bin/rg");
+        assert!(files("%BACKUP%\netc/rg.conf\n").is_empty());
+    }
+
+    #[test]
+    fn dep_names() {
+        assert_eq!(dep_name("glibc"), "glibc");
+        assert_eq!(dep_name("glibc>=2.40"), "glibc");
+        assert_eq!(dep_name("rg=14.1.1"), "rg");
+        assert_eq!(
+            dep_name("bash-completion: bash completion"),
+            "bash-completion"
+        );
+    }
+
+    /// Builds a tar archive with a single `ripgrep-14.1.1-1/desc` entry.
+    fn tar() -> Vec<u8> {
+        let desc = b"%NAME%\nripgrep\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(desc.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        builder
+            .append_data(&mut header, "ripgrep-14.1.1-1/desc", &desc[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    /// Writes `contents` to a file in the temporary directory and reads it back as an archive.
+    fn read_archive(name: &str, contents: &[u8]) -> Vec<(String, String)> {
+        let path = env::temp_dir().join(format!("parui-{}-{name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+
+        let mut archive = open_archive(&path).unwrap();
+        let entries = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).unwrap();
+                (path, contents)
+            })
+            .collect();
+
+        fs::remove_file(&path).unwrap();
+        entries
+    }
+
+    #[test]
+    fn archives() {
+        let expected = [(
+            "ripgrep-14.1.1-1/desc".to_owned(),
+            "%NAME%\nripgrep\n".to_owned(),
+        )];
+
+        assert_eq!(read_archive("plain.db", &tar()), expected);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar()).unwrap();
+        assert_eq!(
+            read_archive("gzip.db", &encoder.finish().unwrap()),
+            expected
+        );
+    }
+}
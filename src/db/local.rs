@@ -0,0 +1,224 @@
+use std::{
+    ffi::OsStr,
+    fs::{File, FileType},
+    io::Read,
+};
+
+use super::{fields, files, list};
+
+const LOCAL_DIR: &str = "/var/lib/pacman/local/";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum InstallReason {
+    #[default]
+    Explicit,
+    Dependency,
+}
+
+impl InstallReason {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            InstallReason::Explicit => "Explicitly installed",
+            InstallReason::Dependency => "Installed as a dependency for another package",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LocalPackage {
+    pub name: Box<str>,
+    pub base: Option<Box<str>>,
+    pub version: Box<str>,
+    pub desc: Box<str>,
+    pub url: Option<Box<str>>,
+    pub arch: Box<str>,
+    pub packager: Box<str>,
+    pub build_date: u64,
+    pub install_date: u64,
+    pub size: u64,
+    pub reason: InstallReason,
+    pub validation: Box<[Box<str>]>,
+    pub licenses: Box<[Box<str>]>,
+    pub groups: Box<[Box<str>]>,
+    pub depends: Box<[Box<str>]>,
+    pub optdepends: Box<[Box<str>]>,
+    pub provides: Box<[Box<str>]>,
+    pub conflicts: Box<[Box<str>]>,
+    pub replaces: Box<[Box<str>]>,
+}
+
+impl LocalPackage {
+    fn parse(desc: &str) -> Option<Self> {
+        let mut package = Self::default();
+
+        for (key, value) in fields(desc) {
+            match key {
+                "NAME" => package.name = value.into(),
+                "BASE" => package.base = Some(value.into()),
+                "VERSION" => package.version = value.into(),
+                "DESC" => package.desc = value.into(),
+                "URL" => package.url = Some(value.into()),
+                "ARCH" => package.arch = value.into(),
+                "PACKAGER" => package.packager = value.into(),
+                "BUILDDATE" => package.build_date = value.parse().unwrap_or_default(),
+                "INSTALLDATE" => package.install_date = value.parse().unwrap_or_default(),
+                "SIZE" => package.size = value.parse().unwrap_or_default(),
+                "REASON" => {
+                    package.reason = match value {
+                        "1" => InstallReason::Dependency,
+                        _ => InstallReason::Explicit,
+                    }
+                }
+                "VALIDATION" => package.validation = list(value),
+                "LICENSE" => package.licenses = list(value),
+                "GROUPS" => package.groups = list(value),
+                "DEPENDS" => package.depends = list(value),
+                "OPTDEPENDS" => package.optdepends = list(value),
+                "PROVIDES" => package.provides = list(value),
+                "CONFLICTS" => package.conflicts = list(value),
+                "REPLACES" => package.replaces = list(value),
+                _ => (),
+            }
+        }
+
+        (!package.name.is_empty()).then_some(package)
+    }
+}
+
+/// Reads every installed package from the local database.
+pub fn read_local_db() -> Vec<LocalPackage> {
+    let Ok(dir) = std::fs::read_dir(LOCAL_DIR) else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::with_capacity(1024);
+
+    let mut path = Vec::with_capacity(256);
+    let dir_os = OsStr::new(LOCAL_DIR);
+    let dir_os_bytes = dir_os.as_encoded_bytes();
+    let dir_len = dir_os_bytes.len();
+    path.extend_from_slice(dir_os_bytes);
+
+    let mut desc = String::with_capacity(4096);
+    for entry in dir.filter_map(Result::ok) {
+        let Ok(true) = entry.file_type().as_ref().map(FileType::is_dir) else {
+            continue;
+        };
+
+        path.extend_from_slice(entry.file_name().as_encoded_bytes());
+        path.extend_from_slice(OsStr::new("/desc").as_encoded_bytes());
+
+        // SAFETY: `path` only contains content that originated from `OsStr::as_encoded_bytes`
+        let file = File::open(unsafe { OsStr::from_encoded_bytes_unchecked(&path) });
+
+        path.truncate(dir_len);
+
+        let Ok(mut file) = file else {
+            continue;
+        };
+
+        desc.clear();
+        if file.read_to_string(&mut desc).is_err() {
+            continue;
+        }
+
+        out.extend(LocalPackage::parse(&desc));
+    }
+
+    out.shrink_to_fit();
+    out
+}
+
+/// Reads the files owned by an installed package from the local database.
+pub fn read_local_files(name: &str, version: &str) -> Option<Box<[Box<str>]>> {
+    let path = format!("{LOCAL_DIR}{name}-{version}/files");
+    let contents = std::fs::read_to_string(path).ok()?;
+
+    Some(files(&contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESC: &str = "%NAME%
+libfoo
+
+%VERSION%
+1:2.3-4
+
+%BASE%
+foo
+
+%DESC%
+Foo as a library
+
+%URL%
+https://example.org/foo
+
+%ARCH%
+x86_64
+
+%BUILDDATE%
+1727000000
+
+%INSTALLDATE%
+1728000000
+
+%PACKAGER%
+Someone <someone@archlinux.org>
+
+%SIZE%
+123456
+
+%REASON%
+1
+
+%LICENSE%
+GPL-3.0-or-later
+
+%VALIDATION%
+sha256
+pgp
+
+%DEPENDS%
+glibc
+zlib>=1.3
+
+%OPTDEPENDS%
+python: for the bindings
+
+%PROVIDES%
+libfoo.so=2-64
+";
+
+    #[test]
+    fn parse_desc() {
+        let package = LocalPackage::parse(DESC).unwrap();
+
+        assert_eq!(&*package.name, "libfoo");
+        assert_eq!(package.base.as_deref(), Some("foo"));
+        assert_eq!(&*package.version, "1:2.3-4");
+        assert_eq!(&*package.desc, "Foo as a library");
+        assert_eq!(package.build_date, 1727000000);
+        assert_eq!(package.install_date, 1728000000);
+        assert_eq!(package.size, 123456);
+        assert!(package.reason == InstallReason::Dependency);
+        assert_eq!(&*package.packager, "Someone <someone@archlinux.org>");
+        assert_eq!(&*package.validation, [Box::from("sha256"), "pgp".into()]);
+        assert_eq!(&*package.depends, [Box::from("glibc"), "zlib>=1.3".into()]);
+        assert_eq!(
+            &*package.optdepends,
+            [Box::from("python: for the bindings")]
+        );
+        assert_eq!(&*package.provides, [Box::from("libfoo.so=2-64")]);
+        assert!(package.conflicts.is_empty());
+    }
+
+    #[test]
+    fn explicit_without_reason() {
+        let package = LocalPackage::parse("%NAME%\nfoo\n\n%VERSION%\n1.0-1\n").unwrap();
+        assert!(package.reason == InstallReason::Explicit);
+        assert!(LocalPackage::parse("%VERSION%\n1.0-1\n").is_none());
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fs, io::Read, ops::ControlFlow, path::Path, sync::Arc};
+
+use super::{fields, files, list, open_archive};
+
+const SYNC_DIR: &str = "/var/lib/pacman/sync/";
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+#[derive(Default)]
+pub struct SyncPackage {
+    pub repo: Arc<str>,
+    pub name: Box<str>,
+    pub base: Option<Box<str>>,
+    pub version: Box<str>,
+    pub desc: Box<str>,
+    pub url: Option<Box<str>>,
+    pub arch: Box<str>,
+    pub packager: Box<str>,
+    pub build_date: u64,
+    pub csize: u64,
+    pub isize: u64,
+    pub licenses: Box<[Box<str>]>,
+    pub groups: Box<[Box<str>]>,
+    pub depends: Box<[Box<str>]>,
+    pub optdepends: Box<[Box<str>]>,
+    pub makedepends: Box<[Box<str>]>,
+    pub checkdepends: Box<[Box<str>]>,
+    pub provides: Box<[Box<str>]>,
+    pub conflicts: Box<[Box<str>]>,
+    pub replaces: Box<[Box<str>]>,
+}
+
+impl SyncPackage {
+    fn parse(repo: &Arc<str>, desc: &str) -> Option<Self> {
+        let mut package = Self {
+            repo: repo.clone(),
+            ..Default::default()
+        };
+
+        for (key, value) in fields(desc) {
+            match key {
+                "NAME" => package.name = value.into(),
+                "BASE" => package.base = Some(value.into()),
+                "VERSION" => package.version = value.into(),
+                "DESC" => package.desc = value.into(),
+                "URL" => package.url = Some(value.into()),
+                "ARCH" => package.arch = value.into(),
+                "PACKAGER" => package.packager = value.into(),
+                "BUILDDATE" => package.build_date = value.parse().unwrap_or_default(),
+                "CSIZE" => package.csize = value.parse().unwrap_or_default(),
+                "ISIZE" => package.isize = value.parse().unwrap_or_default(),
+                "LICENSE" => package.licenses = list(value),
+                "GROUPS" => package.groups = list(value),
+                "DEPENDS" => package.depends = list(value),
+                "OPTDEPENDS" => package.optdepends = list(value),
+                "MAKEDEPENDS" => package.makedepends = list(value),
+                "CHECKDEPENDS" => package.checkdepends = list(value),
+                "PROVIDES" => package.provides = list(value),
+                "CONFLICTS" => package.conflicts = list(value),
+                "REPLACES" => package.replaces = list(value),
+                _ => (),
+            }
+        }
+
+        (!package.name.is_empty()).then_some(package)
+    }
+}
+
+/// Reads every package from the sync databases, in the order the repositories are configured
+/// in `pacman.conf`.
+pub fn read_sync_dbs() -> Vec<SyncPackage> {
+    let mut out = Vec::with_capacity(16384);
+
+    for repo in repos() {
+        let path = Path::new(SYNC_DIR).join(format!("{repo}.db"));
+        let repo: Arc<str> = repo.into();
+
+        let Ok(mut archive) = open_archive(&path) else {
+            continue;
+        };
+
+        let Ok(entries) = archive.entries() else {
+            continue;
+        };
+
+        let mut desc = String::with_capacity(4096);
+        for mut entry in entries.filter_map(Result::ok) {
+            let is_desc = entry
+                .path()
+                .is_ok_and(|path| path.file_name().is_some_and(|name| name == "desc"));
+            if !is_desc {
+                continue;
+            }
+
+            desc.clear();
+            if entry.read_to_string(&mut desc).is_err() {
+                continue;
+            }
+
+            out.extend(SyncPackage::parse(&repo, &desc));
+        }
+    }
+
+    out.shrink_to_fit();
+    out
+}
+
+/// Reads the files a package from `repo` would install from the files database of the
+/// repository, which only exists once it has been downloaded with `pacman -Fy`.
+pub fn read_sync_files(repo: &str, name: &str, version: &str) -> Option<Box<[Box<str>]>> {
+    let path = Path::new(SYNC_DIR).join(format!("{repo}.files"));
+    let mut archive = open_archive(&path).ok()?;
+    let wanted = Path::new(&format!("{name}-{version}")).join("files");
+
+    let mut entry = archive
+        .entries()
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| entry.path().is_ok_and(|path| path == wanted))?;
+
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents).ok()?;
+
+    Some(files(&contents))
+}
+
+/// Calls `visit` with the repository, the `name-version` directory and the contents of the
+/// `files` file of every package in the files databases, until it breaks.
+pub fn visit_sync_files(mut visit: impl FnMut(&str, &str, &str) -> ControlFlow<()>) {
+    let mut contents = String::with_capacity(4096);
+    for repo in repos() {
+        let path = Path::new(SYNC_DIR).join(format!("{repo}.files"));
+        let Ok(mut archive) = open_archive(&path) else {
+            continue;
+        };
+
+        let Ok(entries) = archive.entries() else {
+            continue;
+        };
+
+        for mut entry in entries.filter_map(Result::ok) {
+            let Ok(path) = entry.path() else {
+                continue;
+            };
+            let dir = match (path.file_name(), path.parent()) {
+                (Some(name), Some(dir)) if name == "files" => dir.to_string_lossy().into_owned(),
+                _ => continue,
+            };
+
+            contents.clear();
+            if entry.read_to_string(&mut contents).is_err() {
+                continue;
+            }
+
+            if visit(&repo, &dir, &contents).is_break() {
+                return;
+            }
+        }
+    }
+}
+
+/// Returns the names of the configured repositories, falling back to whatever is in the sync
+/// directory if `pacman.conf` cannot be read.
+fn repos() -> Vec<String> {
+    if let Ok(conf) = fs::read_to_string(PACMAN_CONF) {
+        return conf
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']'))
+            .filter(|section| *section != "options")
+            .map(ToOwned::to_owned)
+            .collect();
+    }
+
+    let Ok(dir) = fs::read_dir(SYNC_DIR) else {
+        return Vec::new();
+    };
+
+    let mut repos = dir
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_suffix(".db")
+                .map(ToOwned::to_owned)
+        })
+        .collect::<Vec<_>>();
+    repos.sort_unstable();
+    repos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESC: &str = "%FILENAME%
+ripgrep-14.1.1-1-x86_64.pkg.tar.zst
+
+%NAME%
+ripgrep
+
+%BASE%
+ripgrep
+
+%VERSION%
+14.1.1-1
+
+%DESC%
+A search tool that combines the usability of ag with the raw speed of grep
+
+%CSIZE%
+1500000
+
+%ISIZE%
+4700000
+
+%URL%
+https://github.com/BurntSushi/ripgrep
+
+%LICENSE%
+MIT
+Unlicense
+
+%ARCH%
+x86_64
+
+%BUILDDATE%
+1727000000
+
+%PACKAGER%
+Someone <someone@archlinux.org>
+
+%DEPENDS%
+gcc-libs
+glibc
+pcre2
+
+%OPTDEPENDS%
+bash-completion: bash completion
+
+%MAKEDEPENDS%
+cargo
+
+%PROVIDES%
+rg=14.1.1
+";
+
+    #[test]
+    fn parse_desc() {
+        let repo: Arc<str> = "extra".into();
+        let package = SyncPackage::parse(&repo, DESC).unwrap();
+
+        assert_eq!(&*package.repo, "extra");
+        assert_eq!(&*package.name, "ripgrep");
+        assert_eq!(package.base.as_deref(), Some("ripgrep"));
+        assert_eq!(&*package.version, "14.1.1-1");
+        assert!(package.desc.starts_with("A search tool"));
+        assert_eq!(
+            package.url.as_deref(),
+            Some("https://github.com/BurntSushi/ripgrep")
+        );
+        assert_eq!(&*package.arch, "x86_64");
+        assert_eq!(package.build_date, 1727000000);
+        assert_eq!(package.csize, 1500000);
+        assert_eq!(package.isize, 4700000);
+        assert_eq!(&*package.licenses, [Box::from("MIT"), "Unlicense".into()]);
+        assert_eq!(package.depends.len(), 3);
+        assert_eq!(&*package.optdepends[0], "bash-completion: bash completion");
+        assert_eq!(&*package.makedepends, [Box::from("cargo")]);
+        assert!(package.checkdepends.is_empty());
+        assert_eq!(&*package.provides, [Box::from("rg=14.1.1")]);
+    }
+
+    #[test]
+    fn parse_desc_without_name() {
+        let repo: Arc<str> = "extra".into();
+        assert!(SyncPackage::parse(&repo, "%VERSION%\n1.0-1\n").is_none());
+        assert!(SyncPackage::parse(&repo, "").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+/// A line of a unified diff.
+pub enum DiffLine {
+    /// The `@@ -a,b +c,d @@` header of a hunk.
+    Hunk(Box<str>),
+    Context(Box<str>),
+    Added(Box<str>),
+    Removed(Box<str>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Computes the unified diff from `old` to `new` line by line, with `context` unchanged lines
+/// around each change.
+///
+/// This uses the quadratic longest common subsequence, which is plenty for files the size of a
+/// PKGBUILD.
+pub fn unified(old: &str, new: &str, context: usize) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    // Every operation along with the positions in `old` and `new` it applies to.
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let op = if i < old.len() && j < new.len() && old[i] == new[j] {
+            Op::Equal
+        } else if j == new.len()
+            || i < old.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]
+        {
+            Op::Delete
+        } else {
+            Op::Insert
+        };
+        ops.push((op, i, j));
+
+        match op {
+            Op::Equal => (i, j) = (i + 1, j + 1),
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+
+    let changes = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, ..))| *op != Op::Equal)
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::new();
+    let mut next = 0;
+    while next < changes.len() {
+        // Changes closer than twice the context share a hunk.
+        let mut last = next;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= context * 2 + 1 {
+            last += 1;
+        }
+
+        let start = changes[next].saturating_sub(context);
+        let end = (changes[last] + context + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let old_len = hunk.iter().filter(|(op, ..)| *op != Op::Insert).count();
+        let new_len = hunk.iter().filter(|(op, ..)| *op != Op::Delete).count();
+        let (_, old_start, new_start) = hunk[0];
+        // Empty ranges are numbered after the line they follow.
+        let old_start = old_start + (old_len > 0) as usize;
+        let new_start = new_start + (new_len > 0) as usize;
+        out.push(DiffLine::Hunk(
+            format!("@@ -{old_start},{old_len} +{new_start},{new_len} @@").into(),
+        ));
+
+        out.extend(hunk.iter().map(|&(op, i, j)| match op {
+            Op::Equal => DiffLine::Context(old[i].into()),
+            Op::Delete => DiffLine::Removed(old[i].into()),
+            Op::Insert => DiffLine::Added(new[j].into()),
+        }));
+
+        next = last + 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders `lines` the way `diff -u` prints them.
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| match line {
+                DiffLine::Hunk(header) => header.to_string(),
+                DiffLine::Context(text) => format!(" {text}"),
+                DiffLine::Added(text) => format!("+{text}"),
+                DiffLine::Removed(text) => format!("-{text}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical() {
+        assert!(unified("a\nb\nc\n", "a\nb\nc\n", 3).is_empty());
+        assert!(unified("", "", 3).is_empty());
+    }
+
+    #[test]
+    fn full_insert() {
+        assert_eq!(
+            render(&unified("", "a\nb\n", 3)),
+            ["@@ -0,0 +1,2 @@", "+a", "+b"]
+        );
+    }
+
+    #[test]
+    fn full_delete() {
+        assert_eq!(
+            render(&unified("a\nb\n", "", 3)),
+            ["@@ -1,2 +0,0 @@", "-a", "-b"]
+        );
+    }
+
+    #[test]
+    fn replaced_line() {
+        assert_eq!(
+            render(&unified("a\nb\nc\n", "a\nB\nc\n", 3)),
+            ["@@ -1,3 +1,3 @@", " a", "-b", "+B", " c"]
+        );
+    }
+
+    #[test]
+    fn hunk_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+
+        // Changes far apart get a hunk each, with the context cut off at the edges.
+        let new = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+        assert_eq!(
+            render(&unified(old, new, 3)),
+            [
+                "@@ -1,5 +1,5 @@",
+                " 1",
+                "-2",
+                "+TWO",
+                " 3",
+                " 4",
+                " 5",
+                "@@ -10,3 +10,4 @@",
+                " 10",
+                " 11",
+                " 12",
+                "+13",
+            ]
+        );
+
+        // Changes closer than twice the context share a hunk.
+        let new = "1\n2\n3\nFOUR\n5\n6\n7\n8\n9\nTEN\n11\n12\n";
+        assert_eq!(
+            render(&unified(old, new, 3)),
+            [
+                "@@ -1,12 +1,12 @@",
+                " 1",
+                " 2",
+                " 3",
+                "-4",
+                "+FOUR",
+                " 5",
+                " 6",
+                " 7",
+                " 8",
+                " 9",
+                "-10",
+                "+TEN",
+                " 11",
+                " 12",
+            ]
+        );
+
+        // Without context, only the changes are shown.
+        assert_eq!(
+            render(&unified(old, "1\n2\n3\n5\n6\n7\n8\n9\n10\n11\n12\n", 0)),
+            ["@@ -4,1 +3,0 @@", "-4"]
+        );
+    }
+}
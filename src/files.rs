@@ -0,0 +1,240 @@
+use std::{collections::HashMap, ops::ControlFlow};
+
+use nohash_hasher::IntSet;
+
+use crate::{
+    db::{file_paths, read_local_files, read_sync_files, visit_sync_files},
+    packages::{Installed, Packages},
+};
+
+/// The files of a package as far as they are known.
+pub enum Listing {
+    Loading,
+    /// Why there is no file list, like a missing files database.
+    Unavailable(Box<str>),
+    Loaded(Box<[Box<str>]>),
+}
+
+/// The files owned by an installed package or installed by a repository package, narrowed down
+/// by a filter.
+pub struct FileList {
+    /// The index of the package the files belong to.
+    pub index: usize,
+    pub listing: Listing,
+    pub filter: String,
+    /// The first visible row of the filtered files.
+    pub scroll: usize,
+}
+
+impl FileList {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            listing: Listing::Loading,
+            filter: String::new(),
+            scroll: 0,
+        }
+    }
+
+    /// Returns the files containing the filter.
+    pub fn filtered(&self) -> Vec<&str> {
+        match &self.listing {
+            Listing::Loaded(files) => files
+                .iter()
+                .map(|file| &**file)
+                .filter(|file| file.contains(&*self.filter))
+                .collect(),
+            Listing::Loading | Listing::Unavailable(_) => Vec::new(),
+        }
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    /// Scrolls down by `rows`, stopping once the last file is visible in a pane `height` rows
+    /// high.
+    pub fn scroll_down(&mut self, rows: usize, height: usize) {
+        let last = self.filtered().len().saturating_sub(height);
+        self.scroll = (self.scroll + rows).min(last.max(self.scroll));
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.filter.push(c);
+        self.scroll = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.filter.pop();
+        self.scroll = 0;
+    }
+}
+
+/// Reads the files of the package at `index`, from the local database if it is installed and
+/// from the files database of its repository otherwise.
+///
+/// The files database is a compressed archive of every package in the repository, so this can
+/// take a moment.
+pub fn read_files(packages: &Packages, installed: &Installed, index: usize) -> Listing {
+    if let Some(local) = installed.get(index) {
+        return match read_local_files(&local.name, &local.version) {
+            Some(files) => Listing::Loaded(files),
+            None => Listing::Unavailable(Box::from("The local database has no file list for it")),
+        };
+    }
+
+    let Some(sync) = packages.get_sync(index) else {
+        return Listing::Unavailable(Box::from(
+            "AUR packages only have a file list once they are installed",
+        ));
+    };
+
+    match read_sync_files(&sync.repo, &sync.name, &sync.version) {
+        Some(files) => Listing::Loaded(files),
+        None => Listing::Unavailable(
+            format!(
+                "No file list in the files database of {}, run `pacman -Fy` to download it",
+                sync.repo
+            )
+            .into(),
+        ),
+    }
+}
+
+/// Whether `file` is `path`, or is called `path` if it is only a file name, like `pacman -F`.
+///
+/// Directories, which end in `/`, are only matched by full paths, as `bin` would otherwise match
+/// every package installing to `/usr/bin/`.
+fn owns(file: &str, path: &str) -> bool {
+    if path.contains('/') {
+        let file = file.trim_start_matches('/').trim_end_matches('/');
+        file == path.trim_start_matches('/').trim_end_matches('/')
+    } else {
+        !file.ends_with('/') && file.rsplit('/').next() == Some(path)
+    }
+}
+
+/// Finds the installed packages owning `path` and the repository packages that would install it,
+/// from the local database and the files databases.
+///
+/// Returns `None` if `cancelled` returns true before the lookup is done.
+pub fn owners(
+    path: &str,
+    packages: &Packages,
+    installed: &Installed,
+    cancelled: impl Fn() -> bool,
+) -> Option<Vec<usize>> {
+    let mut out = IntSet::default();
+
+    for (index, local) in installed.indexed() {
+        if cancelled() {
+            return None;
+        }
+
+        let files = read_local_files(&local.name, &local.version).unwrap_or_default();
+        if files.iter().any(|file| owns(file, path)) {
+            out.insert(index);
+        }
+    }
+
+    let by_dir = packages
+        .sync
+        .iter()
+        .enumerate()
+        .map(|(index, package)| {
+            let dir = format!("{}-{}", package.name, package.version);
+            ((&*package.repo, dir), index)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut was_cancelled = false;
+    visit_sync_files(|repo, dir, contents| {
+        if cancelled() {
+            was_cancelled = true;
+            return ControlFlow::Break(());
+        }
+
+        if file_paths(contents).any(|file| owns(file, path)) {
+            if let Some(&index) = by_dir.get(&(repo, dir.to_owned())) {
+                out.insert(index);
+            }
+        }
+        ControlFlow::Continue(())
+    });
+    if was_cancelled {
+        return None;
+    }
+
+    let mut out = out.into_iter().collect::<Vec<_>>();
+    out.sort_unstable();
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_list(files: &[&str]) -> FileList {
+        FileList {
+            listing: Listing::Loaded(files.iter().map(|&file| file.into()).collect()),
+            ..FileList::new(0)
+        }
+    }
+
+    #[test]
+    fn filtered() {
+        let mut list = file_list(&[
+            "/usr/",
+            "/usr/bin/",
+            "/usr/bin/rg",
+            "/usr/share/man/rg.1.gz",
+        ]);
+        assert_eq!(list.filtered().len(), 4);
+
+        list.push('r');
+        list.push('g');
+        assert_eq!(list.filtered(), ["/usr/bin/rg", "/usr/share/man/rg.1.gz"]);
+        list.pop();
+        assert_eq!(list.filtered().len(), 4);
+
+        assert!(FileList::new(0).filtered().is_empty());
+    }
+
+    #[test]
+    fn scroll_down() {
+        let files = (0..10)
+            .map(|i| format!("/usr/share/doc/{i}"))
+            .collect::<Vec<_>>();
+        let mut list = file_list(&files.iter().map(String::as_str).collect::<Vec<_>>());
+
+        list.scroll_down(3, 4);
+        assert_eq!(list.scroll, 3);
+        // The last file stays at the bottom of the pane.
+        list.scroll_down(10, 4);
+        assert_eq!(list.scroll, 6);
+        list.scroll_up(2);
+        assert_eq!(list.scroll, 4);
+
+        // Filtering resets the scroll, and a list fitting the pane does not scroll.
+        list.push('9');
+        assert_eq!(list.scroll, 0);
+        list.scroll_down(3, 4);
+        assert_eq!(list.scroll, 0);
+    }
+
+    #[test]
+    fn owns_full_paths() {
+        assert!(owns("/usr/bin/rg", "/usr/bin/rg"));
+        assert!(owns("/usr/bin/rg", "usr/bin/rg"));
+        assert!(owns("/usr/bin/", "/usr/bin"));
+        assert!(!owns("/usr/bin/rg", "/bin/rg"));
+    }
+
+    #[test]
+    fn owns_file_names() {
+        assert!(owns("/usr/bin/rg", "rg"));
+        assert!(!owns("/usr/bin/rga", "rg"));
+        assert!(!owns("/usr/bin/", "bin"));
+        assert!(!owns("/usr/lib/", "lib"));
+    }
+}
@@ -1,157 +1,122 @@
 use std::{
     borrow::Cow,
-    collections::HashSet,
-    ffi::OsStr,
-    fs::{File, FileType},
+    collections::HashMap,
     hash::{BuildHasherDefault, DefaultHasher},
-    io::{BufRead, BufReader, Seek},
+    ops::Range,
     sync::Arc,
-    time::Duration,
 };
 
 use compact_strings::FixedCompactStrings;
-use flate2::bufread::GzDecoder;
-use nohash_hasher::IntSet;
+use nohash_hasher::{IntMap, IntSet};
 use parking_lot::RwLock;
-use regex::Regex;
-use tokio::{join, process::Command, time::sleep};
+use serde::Deserialize;
+use tokio::{join, process::Command};
 use tui::{
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span},
 };
 
-use crate::{matcher::Matcher, shown::Shown};
-
-pub async fn list(show_aur: bool) -> FixedCompactStrings {
-    let mut cmd = Command::new("pacman");
-    cmd.arg("-Slq");
+use crate::{
+    action::Action,
+    aur::{self, AurInfo, AUR_URL},
+    config::Keymap,
+    db::{read_local_db, read_sync_dbs, InstallReason, LocalPackage, SyncPackage},
+    diff::DiffLine,
+    files::{FileList, Listing},
+    matcher::Highlighter,
+    mode::Mode,
+    packages::{Installed, Packages},
+    pkgbuild::{highlight, Fetched, PkgbuildView, Source},
+    review::Review,
+    shown::Shown,
+    theme::Theme,
+    transaction::{Operation, Transaction},
+    tree::Tree,
+    version::Version,
+};
 
-    let pacman_out = cmd.output();
-    let aur_out = tokio::task::spawn_blocking(move || {
+pub async fn list(show_aur: bool) -> Packages {
+    let sync = tokio::task::spawn_blocking(read_sync_dbs);
+    let aur = tokio::task::spawn_blocking(move || {
         if show_aur {
-            ureq::get("https://aur.archlinux.org/packages.gz")
-                .call()
-                .ok()
+            aur::packages(AUR_URL)
         } else {
             None
         }
     });
 
-    let (pacman_out, aur_out) = join!(pacman_out, aur_out);
+    let (sync, aur) = join!(sync, aur);
 
     let mut out = FixedCompactStrings::with_capacity(16 * 16384, 16384);
 
-    let Ok(pacman_out) = pacman_out else {
-        return out;
+    let Ok(sync) = sync else {
+        return Packages::new(out, Vec::new(), Vec::new());
     };
 
-    let Ok(aur_out) = aur_out else {
-        return out;
+    let Ok(aur) = aur else {
+        return Packages::new(out, Vec::new(), Vec::new());
     };
 
-    out.extend(
-        pacman_out
-            .stdout
-            .split(|&b| b == b'\n')
-            .filter(|line| !line.is_empty())
-            .filter_map(|line| std::str::from_utf8(line).ok()),
-    );
+    let mut aur = aur.unwrap_or_default();
+    aur.shrink_to_fit();
 
-    if let Some(aur_out) = aur_out {
-        let reader = BufReader::new(aur_out.into_body().into_reader());
-        let decoder = GzDecoder::new(reader);
-
-        if let Ok(s) = std::io::read_to_string(decoder) {
-            out.extend(
-                s.as_bytes()
-                    .split(|&b| b == b'\n')
-                    .filter(|line| !line.is_empty())
-                    .filter_map(|line| std::str::from_utf8(line).ok()),
-            );
-        }
-    }
+    out.extend(sync.iter().map(|package| &*package.name));
+    out.extend(aur.iter().map(|package| &*package.name));
 
     out.shrink_to_fit();
     out.shrink_meta_to_fit();
 
-    out
+    Packages::new(out, sync, aur)
 }
 
-pub fn search(query: &str, packages: &FixedCompactStrings, shown: Arc<RwLock<Shown>>) {
-    let query = query.trim();
-    if query.is_empty() || query == ".*" || query == "." {
-        *shown.write() = Shown::All
-    } else {
-        fn inner<'a>(
-            matcher: &(impl Matcher<&'a str> + ?Sized),
-            packages: &'a FixedCompactStrings,
-            shown: Arc<RwLock<Shown>>,
-        ) {
-            let mut handle = shown.write();
-            match *handle {
-                Shown::Few(_) => {
-                    handle.clear();
-                    handle.extend(
-                        packages
-                            .iter()
-                            .enumerate()
-                            .filter(|(_, package)| matcher.matches(*package))
-                            .map(|(i, _)| i),
-                    )
-                }
-                _ => {
-                    *handle = Shown::Few(
-                        packages
-                            .iter()
-                            .enumerate()
-                            .filter(|(_, package)| matcher.matches(*package))
-                            .map(|(i, _)| i)
-                            .collect(),
-                    )
-                }
-            }
-        }
+/// An optional column of the results list.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Column {
+    Repo,
+    Version,
+    /// The installed version of a package, if it differs from the available one.
+    InstalledVersion,
+    /// The installed and available versions of an outdated package, as in `1.0-1 → 1.1-1`.
+    Upgrade,
+    /// The description of a package, truncated to the space left by the other columns.
+    Description,
+}
 
-        if let Ok(re) = Regex::new(query) {
-            inner(&re, packages, shown);
-        } else {
-            inner(query, packages, shown);
-        }
-    }
+impl Column {
+    pub const DEFAULT: [Column; 4] = [
+        Column::Repo,
+        Column::Version,
+        Column::InstalledVersion,
+        Column::Description,
+    ];
+
+    /// The columns of the upgrades view.
+    pub const UPGRADES: [Column; 3] = [Column::Repo, Column::Upgrade, Column::Description];
 }
 
+const INSTALLED_PREFIX: &str = "installed:";
+/// Shown after the name of packages installed as a dependency.
+const DEPENDENCY_MARKER: &str = " (dep)";
+const MIN_DESCRIPTION_WIDTH: usize = 10;
+
 #[allow(clippy::too_many_arguments)]
 pub fn format_results<'line>(
-    packages: &'line FixedCompactStrings,
+    packages: &'line Packages,
     shown: Arc<RwLock<Shown>>,
     current: usize,
     selected: &IntSet<usize>,
     height: usize,
     pad_to: usize,
     skip: usize,
-    installed: &IntSet<usize>,
+    installed: &Installed,
+    highlight: Option<&Highlighter>,
+    theme: &Theme,
+    columns: &[Column],
+    width: usize,
 ) -> Vec<Line<'line>> {
     use crate::{cows, style};
 
-    const INDEX_STYLE: Style = style!(Color::Gray);
-    const INSTALLED_STYLE: Style = style! {
-        fg: Color::Green,
-        mod: Modifier::BOLD,
-    };
-    const INSTALLED_SELECTED_STYLE: Style = style! {
-        fg: Color::Yellow,
-        bg: Color::Red,
-        mod: Modifier::BOLD,
-    };
-    const UNINSTALLED_STYLE: Style = style! {
-        fg: Color::LightBlue,
-        mod: Modifier::BOLD,
-    };
-    const UNINSTALLED_SELECTED_STYLE: Style = style! {
-        fg: Color::Blue,
-        bg: Color::Red,
-        mod: Modifier::BOLD,
-    };
     const DEFAULT_STYLE: Style = style!();
 
     const PADDINGS: [Cow<'static, str>; 16] = cows!(
@@ -173,111 +138,510 @@ pub fn format_results<'line>(
         "               "
     );
 
-    const SELECTED: Span = Span {
-        content: Cow::Borrowed("!"),
-        style: style! { fg: Color::Yellow, mod: Modifier::BOLD, },
-    };
+    let selected_marker = Span::styled("!", theme.selected_marker);
 
-    match shown.read().get_vec() {
+    let shown = shown.read();
+    let rows = match shown.get_vec() {
         Some(shown) => shown
             .iter()
-            .skip(skip)
-            .take(height - 5)
             .copied()
             .enumerate()
-            .map(|(i, package_idx)| {
-                let real_index = shown[skip + i];
-                let index = i + skip + 1;
-
-                let index_span = Span::styled(index.to_string(), INDEX_STYLE);
-                let padding_span = Span {
-                    content: PADDINGS[pad_to - index.ilog10() as usize].clone(),
-                    style: DEFAULT_STYLE,
-                };
-                let line_span = Span::styled(
-                    &packages[package_idx],
-                    match (installed.contains(&real_index), current == index - 1) {
-                        (true, true) => INSTALLED_SELECTED_STYLE,
-                        (true, false) => INSTALLED_STYLE,
-                        (false, true) => UNINSTALLED_SELECTED_STYLE,
-                        (false, false) => UNINSTALLED_STYLE,
-                    },
-                );
-
-                let spans = if selected.contains(&real_index) {
-                    vec![index_span, padding_span, line_span, SELECTED]
-                } else {
-                    vec![index_span, padding_span, line_span]
-                };
-                Line::from(spans)
-            })
-            .collect(),
-        None => packages
-            .iter()
-            .enumerate()
             .skip(skip)
             .take(height - 5)
-            .map(|(i, line)| {
-                let index_span = Span::styled((i + 1).to_string(), INDEX_STYLE);
-                let padding_span = Span {
-                    content: PADDINGS[pad_to - (i + 1).ilog10() as usize].clone(),
-                    style: DEFAULT_STYLE,
-                };
-                let line_span = Span::styled(
-                    line,
-                    match (installed.contains(&i), current == i) {
-                        (true, true) => INSTALLED_SELECTED_STYLE,
-                        (true, false) => INSTALLED_STYLE,
-                        (false, true) => UNINSTALLED_SELECTED_STYLE,
-                        (false, false) => UNINSTALLED_STYLE,
-                    },
-                );
-
-                let spans = if selected.contains(&i) {
-                    vec![index_span, padding_span, line_span, SELECTED]
-                } else {
-                    vec![index_span, padding_span, line_span]
-                };
-                Line::from(spans)
-            })
+            .collect::<Vec<_>>(),
+        None => (0..packages.len())
+            .map(|i| (i, i))
+            .skip(skip)
+            .take(height - 5)
             .collect(),
+    };
+
+    let installed_version = |i: usize| {
+        let local = installed.get(i)?;
+        (Some(&*local.version) != packages.version(i)).then_some(&*local.version)
+    };
+    let upgrade = |i: usize| {
+        let local = installed.get(i)?;
+        let available = packages.version(i)?;
+        installed
+            .is_outdated(&i)
+            .then(|| format!("{} → {available}", local.version))
+    };
+
+    let is_dependency = |i: usize| {
+        installed
+            .get(i)
+            .is_some_and(|local| local.reason == InstallReason::Dependency)
+    };
+    let name_width = |i: usize| {
+        packages[i].chars().count()
+            + if is_dependency(i) {
+                DEPENDENCY_MARKER.len()
+            } else {
+                0
+            }
+    };
+
+    let max_name_width = rows
+        .iter()
+        .map(|&(_, i)| name_width(i))
+        .max()
+        .unwrap_or_default();
+    let column_width = |column: Column| {
+        rows.iter()
+            .map(|&(_, i)| match column {
+                Column::Repo => packages.repo(i).len(),
+                Column::Version => packages.version(i).map_or(0, |v| v.chars().count()),
+                Column::InstalledVersion => installed_version(i)
+                    .map_or(0, |v| v.chars().count() + INSTALLED_PREFIX.len() + 1),
+                Column::Upgrade => upgrade(i).map_or(0, |v| v.chars().count()),
+                Column::Description => 0,
+            })
+            .max()
+            .unwrap_or_default()
+    };
+
+    // Columns that do not fit are dropped, the description takes up whatever space is left.
+    let mut remaining = width.saturating_sub(pad_to + 1 + max_name_width + 1);
+    let mut widths = Vec::with_capacity(columns.len());
+    for &column in columns {
+        if column == Column::Description {
+            continue;
+        }
+
+        let column_width = column_width(column);
+        if column_width > 0 && column_width < remaining {
+            remaining -= column_width + 1;
+            widths.push((column, column_width));
+        }
+    }
+    if columns.contains(&Column::Description) && remaining > MIN_DESCRIPTION_WIDTH {
+        widths.push((Column::Description, remaining - 1));
+    }
+    widths.sort_by_key(|(column, _)| columns.iter().position(|c| c == column));
+
+    rows.into_iter()
+        .map(|(position, i)| {
+            let index = position + 1;
+            let index_span = Span::styled(index.to_string(), theme.index);
+            let padding_span = Span {
+                content: PADDINGS[pad_to - index.ilog10() as usize].clone(),
+                style: DEFAULT_STYLE,
+            };
+            let style = match (
+                installed.contains(&i),
+                installed.is_outdated(&i),
+                current == position,
+            ) {
+                (true, true, true) => theme.outdated_current,
+                (true, true, false) => theme.outdated,
+                (true, false, true) => theme.installed_current,
+                (true, false, false) => theme.installed,
+                (false, _, true) => theme.uninstalled_current,
+                (false, _, false) => theme.uninstalled,
+            };
+
+            let mut spans = vec![index_span, padding_span];
+            let name = &packages[i];
+            let ranges = highlight
+                .map(|highlight| highlight.ranges(name))
+                .unwrap_or_default();
+            push_highlighted(&mut spans, name, style, theme.matched, &ranges);
+            if is_dependency(i) {
+                spans.push(Span::styled(DEPENDENCY_MARKER, theme.dependency_marker));
+            }
+
+            if selected.contains(&i) {
+                spans.push(selected_marker.clone());
+            } else if !widths.is_empty() {
+                spans.push(Span::raw(" "));
+            }
+
+            if widths.is_empty() {
+                return Line::from(spans);
+            }
+
+            spans.push(Span::raw(" ".repeat(max_name_width - name_width(i))));
+            for &(column, column_width) in &widths {
+                let (text, style) = match column {
+                    Column::Repo => (packages.repo(i).to_owned(), theme.repo),
+                    Column::Version => (
+                        packages.version(i).unwrap_or_default().to_owned(),
+                        theme.version,
+                    ),
+                    Column::InstalledVersion => (
+                        installed_version(i)
+                            .map(|version| format!("{INSTALLED_PREFIX} {version}"))
+                            .unwrap_or_default(),
+                        theme.installed_version,
+                    ),
+                    Column::Upgrade => (upgrade(i).unwrap_or_default(), theme.version),
+                    Column::Description => (
+                        truncate(packages.description(i).unwrap_or_default(), column_width),
+                        theme.description,
+                    ),
+                };
+
+                spans.push(Span::raw(" "));
+                let padding = column_width.saturating_sub(text.chars().count());
+                spans.push(Span::styled(text, style));
+                if column != Column::Description {
+                    spans.push(Span::raw(" ".repeat(padding)));
+                }
+            }
+
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Lists the packages of `transaction`, marking AUR packages and packages that are already
+/// installed, followed by the dependencies that would be pulled in.
+pub fn format_transaction<'line>(
+    transaction: &Transaction,
+    packages: &Packages,
+    installed: &Installed,
+    keymap: &Keymap,
+    theme: &Theme,
+) -> Vec<Line<'line>> {
+    let mut lines = Vec::with_capacity(transaction.targets.len() + 5);
+
+    // The hint comes first so that it is still shown when the packages do not fit.
+    let (confirm, style) = match transaction.operation {
+        Operation::Install | Operation::Upgrade => ("continue", theme.install_hint),
+        Operation::Remove | Operation::RemoveOrphans => ("remove", theme.remove_hint),
+    };
+    let hint = keymap.hint(
+        Mode::Confirm,
+        &[(Action::Confirm, confirm), (Action::Cancel, "cancel")],
+    );
+    lines.push(
+        hint.map(|hint| Line::styled(format!("Press {hint}"), style))
+            .unwrap_or_default(),
+    );
+    lines.push(Line::default());
+
+    for &i in &transaction.targets {
+        let mut spans = vec![Span::raw(packages[i].to_owned())];
+        if let Some(version) = packages.version(i) {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(version.to_owned(), theme.version));
+        }
+        if packages.get_aur(i).is_some() {
+            spans.push(Span::styled(" [aur]", theme.repo));
+        }
+        if !transaction.operation.removes() {
+            if installed.is_outdated(&i) {
+                spans.push(Span::styled(" [upgrade]", theme.outdated));
+            } else if installed.contains(&i) {
+                spans.push(Span::styled(" [reinstall]", theme.installed_version));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+
+    if transaction.operation.removes() {
+        // The helper refuses to remove packages that others still depend on.
+        let targets = transaction
+            .targets
+            .iter()
+            .map(|&i| &packages[i])
+            .collect::<Vec<_>>();
+        let mut warned = false;
+        for &i in &transaction.targets {
+            let Some(package) = installed.get(i) else {
+                continue;
+            };
+
+            let required_by = installed
+                .required_by(package)
+                .into_iter()
+                .filter(|name| !targets.contains(name))
+                .collect::<Vec<_>>();
+            if required_by.is_empty() {
+                continue;
+            }
+
+            if !warned {
+                lines.push(Line::default());
+                warned = true;
+            }
+            lines.push(Line::styled(
+                format!("{} is required by {}", package.name, required_by.join(", ")),
+                theme.remove_hint,
+            ));
+        }
+    } else {
+        lines.push(Line::default());
+        match &transaction.dependencies {
+            None => lines.push(Line::styled("Resolving dependencies...", theme.loading)),
+            Some(dependencies) if dependencies.is_empty() => {
+                lines.push(Line::styled("No dependencies to install", theme.info_key));
+            }
+            Some(dependencies) => {
+                lines.push(Line::styled(
+                    format!("Dependencies ({})", dependencies.len()),
+                    theme.info_key,
+                ));
+                lines.extend(dependencies.iter().map(|dependency| {
+                    Line::from(vec![
+                        Span::styled(format!("{}/", dependency.repo), theme.repo),
+                        Span::raw(dependency.name.to_string()),
+                    ])
+                }));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Draws the visible rows of `tree`, scrolled so that the cursor stays within `height` rows.
+pub fn format_tree<'line>(tree: &Tree, theme: &Theme, height: usize) -> Vec<Line<'line>> {
+    let rows = tree.rows();
+    let skip = (tree.cursor + 1).saturating_sub(height);
+
+    rows.iter()
+        .enumerate()
+        .skip(skip)
+        .take(height)
+        .map(|(position, row)| {
+            let node = row.node;
+            let marker = match (node.expanded, node.expandable()) {
+                (true, _) => "▾ ",
+                (false, true) => "▸ ",
+                (false, false) => "  ",
+            };
+            let style = match (node.installed, position == tree.cursor) {
+                (true, true) => theme.installed_current,
+                (true, false) => theme.installed,
+                (false, true) => theme.uninstalled_current,
+                (false, false) => theme.uninstalled,
+            };
+
+            let mut spans = vec![
+                Span::raw("  ".repeat(row.depth)),
+                Span::raw(marker),
+                Span::styled(node.label.to_string(), style),
+            ];
+            if let Some(label) = node.kind.label() {
+                spans.push(Span::styled(format!(" ({label})"), theme.description));
+            }
+            if node.index.is_none() {
+                spans.push(Span::styled(" (not found)", theme.description));
+            } else if node.expanded && node.children.is_none() {
+                spans.push(Span::styled(" fetching...", theme.loading));
+            }
+
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Formats the filter and count of a file list followed by as many files as fit in `height` rows.
+pub fn format_files<'line>(files: &FileList, theme: &Theme, height: usize) -> Vec<Line<'line>> {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Filter: ", theme.info_key),
+        Span::raw(files.filter.clone()),
+    ])];
+
+    let filtered = match &files.listing {
+        Listing::Loading => {
+            lines.push(Line::styled("Reading files...", theme.loading));
+            return lines;
+        }
+        Listing::Unavailable(reason) => {
+            lines.push(Line::styled(reason.to_string(), theme.description));
+            return lines;
+        }
+        Listing::Loaded(_) => files.filtered(),
+    };
+
+    lines.push(Line::styled(
+        format!("{} files", filtered.len()),
+        theme.description,
+    ));
+    lines.extend(
+        filtered
+            .iter()
+            .skip(files.scroll)
+            .take(height.saturating_sub(2))
+            .map(|file| {
+                let ranges = file
+                    .match_indices(&*files.filter)
+                    .filter(|_| !files.filter.is_empty())
+                    .map(|(start, matched)| start..start + matched.len())
+                    .collect::<Vec<_>>();
+                let mut spans = Vec::new();
+                push_highlighted(&mut spans, file, Style::default(), theme.matched, &ranges);
+                Line::from(
+                    spans
+                        .into_iter()
+                        .map(|span| Span::styled(span.content.into_owned(), span.style))
+                        .collect::<Vec<_>>(),
+                )
+            }),
+    );
+    lines
+}
+
+/// Formats the header describing where a PKGBUILD comes from and its highlighted lines.
+pub fn format_pkgbuild<'line>(
+    view: &PkgbuildView,
+    theme: &Theme,
+) -> (Line<'line>, Vec<Line<'line>>) {
+    match &view.fetched {
+        Fetched::Loading => (
+            Line::styled("Fetching PKGBUILD...", theme.loading),
+            Vec::new(),
+        ),
+        Fetched::Unavailable(reason) => (
+            Line::styled(reason.to_string(), theme.description),
+            Vec::new(),
+        ),
+        Fetched::Loaded(pkgbuild) => {
+            let source = match pkgbuild.source {
+                Source::Cache => "the clone cache of the helper",
+                Source::Aur => "the AUR",
+            };
+            let header = Line::from(vec![
+                Span::styled(format!("PKGBUILD of {}", pkgbuild.base), theme.info_key),
+                Span::styled(format!(", from {source}"), theme.description),
+            ]);
+            (header, highlight(&pkgbuild.text, theme))
+        }
+    }
+}
+
+/// Formats the changes to the PKGBUILD of every package in a review, or the whole PKGBUILD of
+/// those that were never reviewed.
+pub fn format_review<'line>(
+    review: &Review,
+    packages: &Packages,
+    theme: &Theme,
+) -> Vec<Line<'line>> {
+    let mut lines = Vec::new();
+    for entry in &review.entries {
+        let pkgbuild = match &entry.fetched {
+            Fetched::Loading => {
+                lines.push(Line::styled(
+                    packages[entry.index].to_owned(),
+                    theme.info_key,
+                ));
+                lines.push(Line::styled("Fetching PKGBUILD...", theme.loading));
+                lines.push(Line::default());
+                continue;
+            }
+            Fetched::Unavailable(reason) => {
+                lines.push(Line::styled(
+                    packages[entry.index].to_owned(),
+                    theme.info_key,
+                ));
+                lines.push(Line::styled(reason.to_string(), theme.description));
+                lines.push(Line::default());
+                continue;
+            }
+            Fetched::Loaded(pkgbuild) => pkgbuild,
+        };
+
+        lines.push(Line::styled(
+            format!("PKGBUILD of {}", pkgbuild.base),
+            theme.info_key,
+        ));
+        match &entry.diff {
+            None => {
+                lines.push(Line::styled(
+                    "Never reviewed, showing the whole PKGBUILD",
+                    theme.description,
+                ));
+                lines.extend(highlight(&pkgbuild.text, theme));
+            }
+            Some(diff) if diff.is_empty() => {
+                lines.push(Line::styled(
+                    "Unchanged since it was last reviewed",
+                    theme.description,
+                ));
+            }
+            Some(diff) => lines.extend(diff.iter().map(|line| match line {
+                DiffLine::Hunk(hunk) => Line::styled(hunk.to_string(), theme.diff_hunk),
+                DiffLine::Context(text) => Line::raw(format!(" {text}")),
+                DiffLine::Added(text) => Line::styled(format!("+{text}"), theme.diff_added),
+                DiffLine::Removed(text) => Line::styled(format!("-{text}"), theme.diff_removed),
+            })),
+        }
+        lines.push(Line::default());
+    }
+
+    lines
+}
+
+/// Truncates `text` to `width` characters, marking it with an ellipsis if anything was cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_owned();
+    }
+
+    let mut out = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    out.push('…');
+    out
+}
+
+/// Pushes `text` as spans styled with `style`, patching `match_style` onto the `ranges` of it.
+fn push_highlighted<'line>(
+    spans: &mut Vec<Span<'line>>,
+    text: &'line str,
+    style: Style,
+    match_style: Style,
+    ranges: &[Range<usize>],
+) {
+    let mut last = 0;
+    for range in ranges {
+        if range.start > last {
+            spans.push(Span::styled(&text[last..range.start], style));
+        }
+        spans.push(Span::styled(&text[range.clone()], style.patch(match_style)));
+        last = range.end;
+    }
+
+    if last < text.len() || text.is_empty() {
+        spans.push(Span::styled(&text[last..], style));
     }
 }
 
 pub async fn get_info<'line>(
-    all_packages: &FixedCompactStrings,
+    all_packages: &Packages,
     index: usize,
-    installed_cache: &IntSet<usize>,
+    installed_cache: &Installed,
     command: &str,
+    key_style: Style,
 ) -> Vec<Line<'line>> {
     if index >= all_packages.len() {
         return Vec::new();
     }
 
-    let mut cmd = Command::new(command);
+    if let Some(package) = installed_cache.get(index) {
+        return format_local_info(package, installed_cache, key_style);
+    }
 
-    if installed_cache.contains(&index) {
-        cmd.arg("-Qi");
-    } else {
-        // Debounce so that we don't spam requests
-        sleep(Duration::from_millis(200)).await;
+    if let Some(package) = all_packages.get_sync(index) {
+        return format_sync_info(package, key_style);
+    }
 
-        cmd.arg("-Si");
-    };
+    let name = all_packages[index].to_owned();
+    if let Ok(Some(info)) = tokio::task::spawn_blocking(move || aur::info(AUR_URL, &name)).await {
+        return format_aur_info(&info, AUR_URL, key_style);
+    }
 
-    cmd.arg(&all_packages[index]);
+    // Not in the AUR or the RPC is unreachable, let the helper figure it out.
+    let mut cmd = Command::new(command);
+    cmd.arg("-Si").arg(&all_packages[index]);
 
     let output = cmd_output(cmd).await;
     let lines = output.lines().map(ToOwned::to_owned).collect::<Vec<_>>();
 
-    const KEY_STYLE: Style = Style {
-        fg: None,
-        bg: None,
-        underline_color: None,
-        add_modifier: Modifier::BOLD,
-        sub_modifier: Modifier::empty(),
-    };
-
     let mut info = Vec::with_capacity(lines.len());
     for mut line in lines {
         if line.starts_with(' ') {
@@ -288,7 +652,7 @@ pub async fn get_info<'line>(
         if let Some(idx) = line.find(':') {
             let value = line.split_off(idx + 1);
             info.push(Line::from(vec![
-                Span::styled(line, KEY_STYLE),
+                Span::styled(line, key_style),
                 Span::raw(value),
             ]));
         }
@@ -297,64 +661,241 @@ pub async fn get_info<'line>(
     info
 }
 
-pub fn check_installed(packages: &FixedCompactStrings) -> IntSet<usize> {
-    const DIR: &str = "/var/lib/pacman/local/";
+fn format_fields<'line>(
+    fields: impl IntoIterator<Item = (&'static str, String)>,
+    key_style: Style,
+) -> Vec<Line<'line>> {
+    fields
+        .into_iter()
+        .map(|(key, value)| {
+            Line::from(vec![
+                Span::styled(format!("{key:<15} :"), key_style),
+                Span::raw(format!(" {value}")),
+            ])
+        })
+        .collect()
+}
 
-    let Ok(dir) = std::fs::read_dir(DIR) else {
-        return IntSet::default();
+fn format_list(values: &[impl AsRef<str>]) -> String {
+    if values.is_empty() {
+        String::from("None")
+    } else {
+        values
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+fn format_local_info<'line>(
+    package: &LocalPackage,
+    installed: &Installed,
+    key_style: Style,
+) -> Vec<Line<'line>> {
+    let or_none = |value: &Option<Box<str>>| {
+        value
+            .as_deref()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| String::from("None"))
     };
 
-    let mut out = IntSet::with_capacity_and_hasher(512, Default::default());
-    let mut set =
-        HashSet::with_capacity_and_hasher(512, BuildHasherDefault::<DefaultHasher>::default());
+    let validated_by = package
+        .validation
+        .iter()
+        .map(|method| match &**method {
+            "md5" => "MD5 Sum",
+            "sha256" => "SHA-256 Sum",
+            "pgp" => "Signature",
+            _ => "None",
+        })
+        .collect::<Vec<_>>();
+
+    format_fields(
+        [
+            ("Name", package.name.to_string()),
+            ("Version", package.version.to_string()),
+            ("Description", package.desc.to_string()),
+            ("Architecture", package.arch.to_string()),
+            ("URL", or_none(&package.url)),
+            ("Licenses", format_list(&package.licenses)),
+            ("Groups", format_list(&package.groups)),
+            ("Provides", format_list(&package.provides)),
+            ("Depends On", format_list(&package.depends)),
+            ("Optional Deps", format_list(&package.optdepends)),
+            ("Required By", format_list(&installed.required_by(package))),
+            (
+                "Optional For",
+                format_list(&installed.optional_for(package)),
+            ),
+            ("Conflicts With", format_list(&package.conflicts)),
+            ("Replaces", format_list(&package.replaces)),
+            ("Installed Size", format_size(package.size)),
+            ("Packager", package.packager.to_string()),
+            ("Build Date", format_timestamp(package.build_date)),
+            ("Install Date", format_timestamp(package.install_date)),
+            ("Install Reason", package.reason.as_str().to_owned()),
+            ("Validated By", format_list(&validated_by)),
+        ],
+        key_style,
+    )
+}
 
-    let mut path = Vec::with_capacity(256);
-    let dir_os = OsStr::new(DIR);
-    let dir_os_bytes = dir_os.as_encoded_bytes();
-    let dir_len = dir_os_bytes.len();
-    path.extend_from_slice(dir_os_bytes);
+fn format_sync_info<'line>(package: &SyncPackage, key_style: Style) -> Vec<Line<'line>> {
+    let or_none = |value: &Option<Box<str>>| {
+        value
+            .as_deref()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| String::from("None"))
+    };
 
-    let mut reader: Option<BufReader<File>> = None;
-    for entry in dir.filter_map(Result::ok) {
-        let Ok(true) = entry.file_type().as_ref().map(FileType::is_dir) else {
-            continue;
-        };
+    format_fields(
+        [
+            ("Repository", package.repo.to_string()),
+            ("Name", package.name.to_string()),
+            ("Version", package.version.to_string()),
+            ("Description", package.desc.to_string()),
+            ("Architecture", package.arch.to_string()),
+            ("URL", or_none(&package.url)),
+            ("Licenses", format_list(&package.licenses)),
+            ("Groups", format_list(&package.groups)),
+            ("Provides", format_list(&package.provides)),
+            ("Depends On", format_list(&package.depends)),
+            ("Optional Deps", format_list(&package.optdepends)),
+            ("Make Deps", format_list(&package.makedepends)),
+            ("Check Deps", format_list(&package.checkdepends)),
+            ("Conflicts With", format_list(&package.conflicts)),
+            ("Replaces", format_list(&package.replaces)),
+            ("Download Size", format_size(package.csize)),
+            ("Installed Size", format_size(package.isize)),
+            ("Packager", package.packager.to_string()),
+            ("Build Date", format_timestamp(package.build_date)),
+        ],
+        key_style,
+    )
+}
 
-        path.extend_from_slice(entry.file_name().as_encoded_bytes());
-        path.extend_from_slice(OsStr::new("/desc").as_encoded_bytes());
+fn format_aur_info<'line>(info: &AurInfo, base: &str, key_style: Style) -> Vec<Line<'line>> {
+    let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| String::from("None"));
+
+    let fields = [
+        ("Repository", String::from("aur")),
+        ("Name", info.name.clone()),
+        ("Version", info.version.clone()),
+        ("Description", or_none(&info.description)),
+        ("Groups", format_list(&info.groups)),
+        ("URL", or_none(&info.url)),
+        ("AUR URL", format!("{base}/packages/{}", info.name)),
+        ("Licenses", format_list(&info.license)),
+        ("Provides", format_list(&info.provides)),
+        ("Depends On", format_list(&info.depends)),
+        ("Make Deps", format_list(&info.make_depends)),
+        ("Check Deps", format_list(&info.check_depends)),
+        ("Optional Deps", format_list(&info.opt_depends)),
+        ("Conflicts With", format_list(&info.conflicts)),
+        ("Replaces", format_list(&info.replaces)),
+        ("Maintainer", or_none(&info.maintainer)),
+        ("Co-Maintainers", format_list(&info.co_maintainers)),
+        ("Submitter", or_none(&info.submitter)),
+        ("Votes", info.num_votes.to_string()),
+        ("Popularity", format!("{:.6}", info.popularity)),
+        ("Keywords", format_list(&info.keywords)),
+        ("First Submitted", format_timestamp(info.first_submitted)),
+        ("Last Modified", format_timestamp(info.last_modified)),
+        (
+            "Out Of Date",
+            info.out_of_date
+                .map(format_timestamp)
+                .unwrap_or_else(|| String::from("No")),
+        ),
+        (
+            "Snapshot URL",
+            info.url_path
+                .as_ref()
+                .map(|path| format!("{base}{path}"))
+                .unwrap_or_else(|| String::from("None")),
+        ),
+        ("Git Clone URL", format!("{base}/{}.git", info.package_base)),
+    ];
+
+    format_fields(fields, key_style)
+}
 
-        // SAFETY: `path` only contains content that originated from `OsStr::as_encoded_bytes`
-        let Ok(file) = File::open(unsafe { OsStr::from_encoded_bytes_unchecked(&path) }) else {
-            continue;
-        };
+/// Formats a size in bytes the same way pacman does.
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
 
-        unsafe {
-            path.set_len(dir_len);
-        }
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 2048.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
 
-        match reader {
-            Some(ref mut reader) => {
-                reader.rewind().unwrap();
-                *reader.get_mut() = file;
-            }
-            None => {
-                reader = Some(BufReader::new(file));
-            }
-        }
+    format!("{size:.2} {}", UNITS[unit])
+}
 
-        if let Some(Ok(name)) = reader.as_mut().unwrap().lines().nth(1) {
-            set.insert(name);
-        };
-    }
+/// Formats a UNIX timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
+pub(crate) fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // Howard Hinnant's `civil_from_days`
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+pub fn check_installed(packages: &Packages) -> Installed {
+    match_installed(packages, read_local_db())
+}
 
-    for (pos, _) in packages
+/// Finds the installed packages among `packages` and which of them are outdated.
+fn match_installed(packages: &Packages, local: Vec<LocalPackage>) -> Installed {
+    let mut names = HashMap::with_capacity_and_hasher(
+        local.len(),
+        BuildHasherDefault::<DefaultHasher>::default(),
+    );
+    names.extend(
+        local
+            .iter()
+            .enumerate()
+            .map(|(pos, package)| (&*package.name, pos)),
+    );
+
+    let by_index = packages
         .iter()
         .enumerate()
-        .filter(|(_, p)| set.contains(*p))
-    {
-        out.insert(pos);
-    }
-    out
+        .filter_map(|(pos, name)| Some((pos, *names.get(name)?)))
+        .collect::<IntMap<_, _>>();
+
+    drop(names);
+
+    // Packages whose installed version is older than the one available.
+    let outdated = by_index
+        .iter()
+        .filter(|&(&pos, &local_pos)| {
+            packages
+                .version(pos)
+                .is_some_and(|version| Version(&local[local_pos].version) < Version(version))
+        })
+        .map(|(&pos, _)| pos)
+        .collect::<IntSet<_>>();
+
+    Installed::new(local, by_index, outdated)
 }
 
 async fn cmd_output(mut cmd: Command) -> String {
@@ -364,3 +905,66 @@ async fn cmd_output(mut cmd: Command) -> String {
         .and_then(|output| String::from_utf8(output.stdout).ok())
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::aur::AurPackage;
+
+    use super::*;
+
+    fn sync(name: &str, version: &str) -> SyncPackage {
+        SyncPackage {
+            name: name.into(),
+            version: version.into(),
+            ..SyncPackage::default()
+        }
+    }
+
+    fn aur(name: &str, version: &str) -> AurPackage {
+        AurPackage {
+            name: name.into(),
+            version: version.into(),
+            description: None,
+            maintainer: None,
+            num_votes: 0,
+        }
+    }
+
+    fn local(name: &str, version: &str) -> LocalPackage {
+        LocalPackage {
+            name: name.into(),
+            version: version.into(),
+            ..LocalPackage::default()
+        }
+    }
+
+    #[test]
+    fn outdated_packages() {
+        let packages = Packages::new(
+            ["bash", "git", "vim", "paru", "yay"].into_iter().collect(),
+            vec![
+                sync("bash", "5.2.037-1"),
+                sync("git", "2.47.1-1"),
+                sync("vim", "9.1.0866-1"),
+            ],
+            vec![aur("paru", "2.0.4-1"), aur("yay", "12.4.2-1")],
+        );
+        let installed = match_installed(
+            &packages,
+            vec![
+                local("bash", "5.2.037-1"),
+                local("git", "2.47.0-1"),
+                // Newer than the repositories, like a package built from a local PKGBUILD.
+                local("vim", "1:9.0-1"),
+                local("paru", "2.0.3-2"),
+                local("not-in-any-repo", "1.0-1"),
+            ],
+        );
+
+        assert!(installed.contains(&0));
+        assert!(installed.contains(&3));
+        assert!(!installed.contains(&4));
+        assert_eq!(installed.get(3).map(|package| &*package.name), Some("paru"));
+        assert_eq!(installed.outdated(), [1, 3]);
+    }
+}
@@ -1,47 +1,76 @@
 use std::{
     env,
-    io::{self, BufWriter, Write},
+    io::{self, Write},
     os::unix::prelude::CommandExt,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, OnceLock,
     },
     time::Duration,
 };
 
+use action::Action;
 use atomic::Atomic;
-use compact_strings::FixedCompactStrings;
+use aur::AUR_URL;
 use config::Config;
 use crossterm::{
     event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
-use interface::{check_installed, format_results, get_info, list, search};
+use db::InstallReason;
+use files::{read_files, FileList};
+use interface::{
+    check_installed, format_files, format_pkgbuild, format_results, format_review,
+    format_transaction, format_tree, get_info, list, Column,
+};
 use libc::malloc_trim;
+use matcher::Highlighter;
 use message::Message;
-use mode::Mode;
+use mode::{Mode, View};
 use nohash_hasher::IntSet;
+use packages::{Installed, Packages};
 use parking_lot::{Mutex, RwLock};
+use pkgbuild::{fetch as fetch_pkgbuild, PkgbuildView};
+use review::{Entry, Review, Reviewed};
+use search::{Search, SearchError, Searcher};
 use shown::Shown;
+use transaction::{resolve, Operation, Transaction};
+use tree::{Direction, Tree};
 use tui::{
     backend::CrosstermBackend,
     layout::{Alignment, Rect, Size},
-    style::{Color, Modifier, Style, Stylize},
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
     Terminal,
 };
 
+mod action;
+mod aur;
 mod config;
+mod db;
+mod diff;
+mod files;
 mod interface;
 mod libc;
 mod macros;
 mod matcher;
 mod message;
 mod mode;
+mod packages;
+mod pkgbuild;
+mod query;
+mod review;
+mod search;
 mod shown;
+mod theme;
+mod transaction;
+mod tree;
+mod version;
 mod widgets;
 
+/// How long the cursor has to rest on an AUR package before its info is requested.
+const INFO_DEBOUNCE: Duration = Duration::from_millis(200);
+
 #[cfg(feature = "dhat")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
@@ -53,6 +82,9 @@ async fn main() -> Result<(), io::Error> {
 
     let args = Config::new(env::args());
     let command = args.command;
+    let flags = args.flags;
+    let keymap = args.keymap;
+    let theme = args.theme;
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -66,15 +98,33 @@ async fn main() -> Result<(), io::Error> {
     let mut selected = IntSet::default();
     let mut info_scroll: u16 = 0;
     let mut insert_pos = query.len() as u16;
+    let mut descriptions = false;
+    let mut fuzzy = false;
+    // What was shown before jumping from the tree to a package that was not among the results,
+    // restored when the search for the package is cancelled.
+    let mut jumped_from: Option<Previous> = None;
 
     let shown = Arc::new(RwLock::new(Shown::Few(Vec::new())));
     let mode = Arc::new(Atomic::new(Mode::Insert));
+    let view = Atomic::new(View::Search);
     let info = Arc::new(Mutex::new(Vec::new()));
     let redraw = Arc::new(AtomicBool::new(true));
 
-    let all_packages: Arc<OnceLock<FixedCompactStrings>> = Arc::new(OnceLock::new());
-    let installed: Arc<OnceLock<IntSet<usize>>> = Arc::new(OnceLock::new());
+    let all_packages: Arc<OnceLock<Packages>> = Arc::new(OnceLock::new());
+    // Refreshed whenever the helper returns to parui.
+    let installed: Arc<OnceLock<RwLock<Arc<Installed>>>> = Arc::new(OnceLock::new());
     let error_msg = Arc::new(Atomic::new(Message::TrySearch));
+    let highlight: Arc<Mutex<Option<Box<Highlighter>>>> = Arc::new(Mutex::new(None));
+    // The transaction waiting to be confirmed.
+    let transaction: Arc<Mutex<Option<Transaction>>> = Arc::new(Mutex::new(None));
+    // The dependency tree of the package it was opened on.
+    let tree: Arc<Mutex<Option<Tree>>> = Arc::new(Mutex::new(None));
+    // The file list of the package it was opened on.
+    let files: Arc<Mutex<Option<FileList>>> = Arc::new(Mutex::new(None));
+    // The PKGBUILD of the package it was opened on.
+    let pkgbuild: Arc<Mutex<Option<PkgbuildView>>> = Arc::new(Mutex::new(None));
+    // The PKGBUILDs to review before running the helper.
+    let review: Arc<Mutex<Option<Review>>> = Arc::new(Mutex::new(None));
 
     let shown_len = || {
         shown
@@ -84,18 +134,27 @@ async fn main() -> Result<(), io::Error> {
     };
     let real_idx = |idx| shown.read().get(idx).unwrap_or(idx);
 
+    let searcher = Arc::new(Searcher::default());
+    searcher.start(Search::new(&query, descriptions, fuzzy));
+    // The id of the search whose results get selected once it is done, set by searching with
+    // the live search still running.
+    let select_when_done = Arc::new(AtomicUsize::new(0));
+
     let mut search_task = {
-        let query = query.clone();
         let mode = mode.clone();
         let shown = shown.clone();
         let error_msg = error_msg.clone();
         let redraw = redraw.clone();
-        let command = command.clone();
+        let show_aur = args.aur;
         let all_packages = all_packages.clone();
         let installed = installed.clone();
+        let highlight = highlight.clone();
+        let searcher = searcher.clone();
+        let select_when_done = select_when_done.clone();
 
         Some(tokio::spawn(async move {
-            if query.is_empty() {
+            let initial = searcher.latest();
+            if initial.query.is_empty() {
                 error_msg.store(Message::ListingPackages, Ordering::SeqCst);
             } else {
                 error_msg.store(Message::Searching, Ordering::SeqCst);
@@ -104,32 +163,216 @@ async fn main() -> Result<(), io::Error> {
             redraw.store(true, Ordering::SeqCst);
 
             if all_packages.get().is_none() {
-                let result = list(command != "pacman").await;
-                installed.get_or_init(|| check_installed(&result));
+                let result = list(show_aur).await;
+                let checked = check_installed(&result);
+                // PKGBUILDs reviewed before the helper replaced parui are stored now that it is
+                // known which packages got built.
+                let _ = review::commit_pending(&checked);
+                installed.get_or_init(|| RwLock::new(Arc::new(checked)));
                 all_packages.get_or_init(|| result);
             }
 
-            search(&query, all_packages.get().unwrap(), shown.clone());
+            // The query may have been edited while the packages were being listed.
+            let search = searcher.latest();
+            let id = searcher.start(search.clone());
+            let result = searcher.run(
+                id,
+                &search,
+                all_packages.get().unwrap(),
+                &installed.get().unwrap().read(),
+                &shown,
+            );
+
+            match result {
+                Ok(matcher) => *highlight.lock() = matcher,
+                Err(SearchError::Invalid(message)) => {
+                    error_msg.store(message, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+                    return;
+                }
+                Err(SearchError::Cancelled) => (),
+            }
 
             if shown
                 .read()
                 .len()
                 .unwrap_or(all_packages.get().map(|p| p.len()).unwrap_or_default())
-                != 0
+                == 0
             {
-                mode.store(Mode::Select, Ordering::SeqCst);
-            } else {
                 error_msg.store(Message::NoResults, Ordering::SeqCst);
+            } else if search == initial
+                || select_when_done
+                    .compare_exchange(id, 0, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            {
+                mode.store(Mode::Select, Ordering::SeqCst);
             }
             redraw.store(true, Ordering::SeqCst);
         }))
     };
 
+    // Searches as the query is edited, cancelling the search for the previous edit if it is still
+    // running. Before the packages are listed, this only records the search for the listing task.
+    //
+    // Returns the id the search was started as.
+    let live_search = |search: Search| {
+        view.store(View::Search, Ordering::SeqCst);
+        let id = searcher.start(search.clone());
+        if all_packages.get().is_none() {
+            return id;
+        }
+
+        let searcher = searcher.clone();
+        let all_packages = all_packages.clone();
+        let installed = installed.clone();
+        let shown = shown.clone();
+        let highlight = highlight.clone();
+        let info = info.clone();
+        let error_msg = error_msg.clone();
+        let redraw = redraw.clone();
+        let mode = mode.clone();
+        let select_when_done = select_when_done.clone();
+
+        tokio::task::spawn_blocking(move || {
+            match searcher.run(
+                id,
+                &search,
+                all_packages.get().unwrap(),
+                &installed.get().unwrap().read(),
+                &shown,
+            ) {
+                Ok(matcher) => {
+                    *highlight.lock() = matcher;
+                    let select = select_when_done
+                        .compare_exchange(id, 0, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok();
+                    if shown.read().len() == Some(0) {
+                        error_msg.store(Message::NoResults, Ordering::SeqCst);
+                        // There is nothing left to select.
+                        let _ = mode.compare_exchange(
+                            Mode::Select,
+                            Mode::Insert,
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        );
+                    } else if select {
+                        mode.store(Mode::Select, Ordering::SeqCst);
+                    }
+                }
+                Err(SearchError::Invalid(message)) => error_msg.store(message, Ordering::SeqCst),
+                Err(SearchError::Cancelled) => return,
+            }
+            info.lock().clear();
+            redraw.store(true, Ordering::SeqCst);
+        });
+
+        id
+    };
+
+    // Fetches the dependencies of the AUR package at `index` for the node of the tree at `path`.
+    let fetch_dependencies = |path: Vec<usize>, index: usize| {
+        let tree = tree.clone();
+        let all_packages = all_packages.clone();
+        let installed = installed.get().unwrap().read().clone();
+        let redraw = redraw.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let packages = all_packages.get().unwrap();
+            let info = aur::info(AUR_URL, &packages[index]);
+            if let Some(tree) = tree.lock().as_mut() {
+                tree.set_children(&path, index, info.as_ref(), packages, &installed);
+            }
+            redraw.store(true, Ordering::SeqCst);
+        });
+    };
+
+    // Shows the PKGBUILDs of the AUR packages `pending` builds before running the helper, or
+    // returns the handoff right away if there are none. Dependencies that have not been resolved
+    // yet are looked up first.
+    let review_first = |pending: Transaction, handoff: (std::process::Command, Vec<u8>)| {
+        let (Some(packages), Some(installed)) = (all_packages.get(), installed.get()) else {
+            return Some(handoff);
+        };
+
+        // Official packages never depend on AUR packages.
+        let aur = pending.aur_packages(packages);
+        if aur.is_empty() {
+            return Some(handoff);
+        }
+
+        let mut new = Review::new(&aur, handoff);
+        new.resolving = pending.dependencies.is_none();
+        *review.lock() = Some(new);
+        mode.store(Mode::Review, Ordering::SeqCst);
+        redraw.store(true, Ordering::SeqCst);
+
+        for &index in &aur {
+            let all_packages = all_packages.clone();
+            let installed = installed.read().clone();
+            let review = review.clone();
+            let redraw = redraw.clone();
+
+            tokio::task::spawn_blocking(move || {
+                review_entry(
+                    all_packages.get().unwrap(),
+                    &installed,
+                    index,
+                    &review,
+                    &redraw,
+                );
+            });
+        }
+
+        if pending.dependencies.is_none() {
+            let all_packages = all_packages.clone();
+            let installed = installed.read().clone();
+            let review = review.clone();
+            let redraw = redraw.clone();
+
+            tokio::task::spawn_blocking(move || {
+                let mut pending = pending;
+                let packages = all_packages.get().unwrap();
+                pending.dependencies = Some(resolve(packages, &installed, &pending.targets));
+
+                let added = {
+                    let mut review = review.lock();
+                    // The review may have been cancelled or replaced in the meantime, in which
+                    // case it does not start with the same packages.
+                    let Some(review) = review.as_mut().filter(|review| {
+                        review.resolving
+                            && review
+                                .entries
+                                .iter()
+                                .map(|entry| entry.index)
+                                .take(aur.len())
+                                .eq(aur.iter().copied())
+                    }) else {
+                        return;
+                    };
+                    review.resolving = false;
+                    pending
+                        .aur_packages(packages)
+                        .into_iter()
+                        .filter(|&index| review.add(index))
+                        .collect::<Vec<_>>()
+                };
+                redraw.store(true, Ordering::SeqCst);
+
+                for index in added {
+                    review_entry(packages, &installed, index, &review, &redraw);
+                }
+            });
+        }
+        None
+    };
+
     terminal.clear()?;
 
     let mut title_state = widgets::TitleState::new();
 
     loop {
+        // Results may have shrunk under the cursor when a search finished in the background.
+        current = current.min(shown_len().saturating_sub(1));
         let mut line = current;
         let size = terminal.size();
         let Ok(size) = size else {
@@ -140,6 +383,7 @@ async fn main() -> Result<(), io::Error> {
             continue;
         }
 
+        let split = (size.width as u32 * args.list_ratio as u32 / 100) as u16;
         let per_page = (size.height - 5) as usize;
         let page = current / per_page;
         let skipped = page * per_page;
@@ -160,38 +404,38 @@ async fn main() -> Result<(), io::Error> {
                 }
                 search_task = Some(tokio::spawn(async move {
                     let real_idx = shown.read().get(current).unwrap_or(current);
-                    *info.lock() = get_info(
-                        all_packages.get().unwrap(),
-                        real_idx,
-                        installed.get().unwrap(),
-                        &command,
-                    )
-                    .await;
+                    let installed = installed.get().unwrap().read().clone();
+                    let packages = all_packages.get().unwrap();
+
+                    // Moving the cursor aborts this task, so scrolling through AUR packages does
+                    // not send a request for every one of them.
+                    if packages.get_aur(real_idx).is_some() && installed.get(real_idx).is_none() {
+                        tokio::time::sleep(INFO_DEBOUNCE).await;
+                    }
+
+                    *info.lock() =
+                        get_info(packages, real_idx, &installed, &command, theme.info_key).await;
 
                     redraw.store(true, Ordering::SeqCst);
                 }))
             }
 
             terminal.draw(|f| {
-                let search_color;
-                let shown_color;
-                let search_mod;
-                match mode.load(Ordering::SeqCst) {
-                    Mode::Insert => {
-                        search_color = Color::White;
-                        shown_color = Color::Gray;
-                        search_mod = Modifier::BOLD;
-                    }
-                    Mode::Select => {
-                        search_color = Color::Gray;
-                        shown_color = Color::White;
-                        search_mod = Modifier::default();
-                    }
+                let (search_style, shown_style) = match mode.load(Ordering::SeqCst) {
+                    Mode::Insert => (theme.active(Mode::Insert), theme.inactive),
+                    mode @ (Mode::Select
+                    | Mode::Confirm
+                    | Mode::Tree
+                    | Mode::Files
+                    | Mode::Pkgbuild
+                    | Mode::Review) => (theme.inactive, theme.active(mode)),
                 };
 
                 title_state.query = query.clone();
-                title_state.col = search_color;
-                title_state.mod_ = search_mod;
+                title_state.descriptions = descriptions;
+                title_state.fuzzy = fuzzy;
+                title_state.view = view.load(Ordering::SeqCst);
+                title_state.style = search_style;
                 title_state.size = size.into();
                 f.render_stateful_widget(
                     widgets::Title::new(),
@@ -207,7 +451,7 @@ async fn main() -> Result<(), io::Error> {
                 f.render_widget(
                     Block::default()
                         .borders(Borders::ALL)
-                        .border_style(Style::default().fg(shown_color))
+                        .border_style(shown_style)
                         .border_type(BorderType::Rounded),
                     Rect {
                         x: 0,
@@ -224,10 +468,12 @@ async fn main() -> Result<(), io::Error> {
                         width: size.width / 2,
                         height: 4,
                     };
-                    let no_shown = Paragraph::new(error_msg.load(Ordering::SeqCst).as_str())
+                    let message = error_msg.load(Ordering::SeqCst);
+                    let no_shown = Paragraph::new(message.as_str())
+                        .style(theme.popup)
                         .block(
                             Block::default()
-                                .title(" No Results ".bold())
+                                .title(Span::styled(message.title(), theme.popup_title))
                                 .title_alignment(Alignment::Center)
                                 .borders(Borders::ALL)
                                 .border_type(BorderType::Rounded),
@@ -243,10 +489,16 @@ async fn main() -> Result<(), io::Error> {
                     return;
                 }
 
+                let columns = match view.load(Ordering::SeqCst) {
+                    View::Search | View::Orphans => &args.columns[..],
+                    View::Upgrades => &Column::UPGRADES[..],
+                };
+
                 // this is technically stateful, but it is hard to incrementally update so we will
                 // reconstruct it instead.
                 if let Some(formatted) = all_packages.get().and_then(|all_packages| {
                     installed.get().map(|installed| {
+                        let installed = installed.read();
                         format_results(
                             all_packages,
                             shown.clone(),
@@ -255,7 +507,11 @@ async fn main() -> Result<(), io::Error> {
                             size.height as usize,
                             shown_len_str_len,
                             skipped,
-                            installed,
+                            &installed,
+                            highlight.lock().as_deref(),
+                            &theme,
+                            columns,
+                            split.saturating_sub(3) as usize,
                         )
                     })
                 }) {
@@ -264,63 +520,195 @@ async fn main() -> Result<(), io::Error> {
                         Rect {
                             x: 2,
                             y: 4,
-                            width: size.width / 2 - 3,
+                            width: split.saturating_sub(3),
                             height: size.height - 4,
                         },
                     );
                 }
 
                 let area = Rect {
-                    x: size.width / 2,
+                    x: split,
                     y: 4,
-                    width: size.width / 2 - 1,
+                    width: (size.width - split).saturating_sub(1),
                     height: size.height - 5,
                 };
                 let border = Block::default()
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(shown_color))
+                    .border_style(shown_style)
                     .border_type(BorderType::Rounded);
                 f.render_widget(Clear, area);
                 f.render_widget(border, area);
 
+                if let Some(tree) = tree
+                    .lock()
+                    .as_ref()
+                    .filter(|_| mode.load(Ordering::SeqCst) == Mode::Tree)
+                {
+                    let width = (size.width - split).saturating_sub(5);
+                    let hint = keymap
+                        .hint(
+                            Mode::Tree,
+                            &[
+                                (Action::Jump, "go to the package"),
+                                (Action::Cancel, "go back"),
+                            ],
+                        )
+                        .map(|hint| Line::styled(format!("Press {hint}"), theme.install_hint));
+                    f.render_widget(
+                        Paragraph::new(hint.unwrap_or_default()),
+                        Rect {
+                            x: split + 2,
+                            y: 5,
+                            width,
+                            height: 1,
+                        },
+                    );
+                    f.render_widget(
+                        Paragraph::new(format_tree(tree, &theme, (size.height - 9) as usize)),
+                        Rect {
+                            x: split + 2,
+                            y: 7,
+                            width,
+                            height: size.height - 9,
+                        },
+                    );
+
+                    unsafe {
+                        malloc_trim(0);
+                    }
+                    return;
+                }
+
+                if let Some(files) = files
+                    .lock()
+                    .as_ref()
+                    .filter(|_| mode.load(Ordering::SeqCst) == Mode::Files)
+                {
+                    let width = (size.width - split).saturating_sub(5);
+                    let hint = match keymap.hint(Mode::Files, &[(Action::Cancel, "go back")]) {
+                        Some(hint) => format!("Type to filter the files, {hint}"),
+                        None => "Type to filter the files".to_owned(),
+                    };
+                    f.render_widget(
+                        Paragraph::new(Line::styled(hint, theme.install_hint)),
+                        Rect {
+                            x: split + 2,
+                            y: 5,
+                            width,
+                            height: 1,
+                        },
+                    );
+                    f.render_widget(
+                        Paragraph::new(format_files(files, &theme, (size.height - 9) as usize)),
+                        Rect {
+                            x: split + 2,
+                            y: 7,
+                            width,
+                            height: size.height - 9,
+                        },
+                    );
+
+                    unsafe {
+                        malloc_trim(0);
+                    }
+                    return;
+                }
+
+                if let Some(view) = pkgbuild
+                    .lock()
+                    .as_ref()
+                    .filter(|_| mode.load(Ordering::SeqCst) == Mode::Pkgbuild)
+                {
+                    let width = (size.width - split).saturating_sub(5);
+                    let (header, body) = format_pkgbuild(view, &theme);
+                    let hint = keymap
+                        .hint(Mode::Pkgbuild, &[(Action::Cancel, "go back")])
+                        .map(|hint| Line::styled(format!("Press {hint}"), theme.install_hint));
+                    f.render_widget(
+                        Paragraph::new(vec![hint.unwrap_or_default(), Line::default(), header]),
+                        Rect {
+                            x: split + 2,
+                            y: 5,
+                            width,
+                            height: 3,
+                        },
+                    );
+                    f.render_widget(
+                        Paragraph::new(body).scroll((view.scroll as u16, 0)),
+                        Rect {
+                            x: split + 2,
+                            y: 9,
+                            width,
+                            height: pane_height(size) as u16,
+                        },
+                    );
+
+                    unsafe {
+                        malloc_trim(0);
+                    }
+                    return;
+                }
+
                 let (info, no_info) = {
                     let info_lock = info.lock();
                     (info_lock.clone(), info_lock.is_empty())
                 };
 
                 // TODO: Use render_widget_ref when it is ready.
-                let actions = Paragraph::new(if no_info {
-                    vec![
-                        "Press ENTER to (re)install selected packages"
-                            .green()
-                            .bold()
-                            .into(),
-                        "Press Shift-R to uninstall selected packages"
-                            .red()
-                            .bold()
-                            .into(),
-                        Line::default(),
-                        "Finding info...".gray().into(),
-                    ]
-                } else {
-                    vec![
-                        "Press ENTER to (re)install selected packages"
-                            .green()
-                            .bold()
-                            .into(),
-                        "Press Shift-R to uninstall selected packages"
-                            .red()
-                            .bold()
-                            .into(),
-                    ]
-                })
-                .alignment(Alignment::Left);
+                let hints: &[_] = match view.load(Ordering::SeqCst) {
+                    View::Search => &[
+                        (
+                            Action::Install,
+                            "(re)install selected packages",
+                            theme.install_hint,
+                        ),
+                        (
+                            Action::Remove,
+                            "uninstall selected packages",
+                            theme.remove_hint,
+                        ),
+                    ],
+                    View::Upgrades => &[
+                        (
+                            Action::Install,
+                            "upgrade selected packages",
+                            theme.install_hint,
+                        ),
+                        (
+                            Action::SystemUpgrade,
+                            "upgrade the whole system",
+                            theme.install_hint,
+                        ),
+                    ],
+                    View::Orphans => &[
+                        (Action::Remove, "remove selected orphans", theme.remove_hint),
+                        (
+                            Action::RemoveOrphans,
+                            "remove every orphan",
+                            theme.remove_hint,
+                        ),
+                    ],
+                };
+                let mut hints: Vec<_> = hints
+                    .iter()
+                    .map(|&(action, description, style)| {
+                        keymap
+                            .hint(Mode::Select, &[(action, description)])
+                            .map(|hint| Line::styled(format!("Press {hint}"), style))
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                if no_info {
+                    hints.push(Line::default());
+                    hints.push(Line::styled("Finding info...", theme.loading));
+                }
+                let actions = Paragraph::new(hints).alignment(Alignment::Left);
                 f.render_widget(
                     actions,
                     Rect {
-                        x: size.width / 2 + 2,
+                        x: split + 2,
                         y: 5,
-                        width: size.width / 2 - 5,
+                        width: (size.width - split).saturating_sub(5),
                         height: 2 + no_info as u16 * 2,
                     },
                 );
@@ -332,13 +720,111 @@ async fn main() -> Result<(), io::Error> {
                 f.render_widget(
                     info,
                     Rect {
-                        x: size.width / 2 + 2,
+                        x: split + 2,
                         y: 8 - no_info as u16,
-                        width: size.width / 2 - 5,
+                        width: (size.width - split).saturating_sub(5),
                         height: size.height - 10 - no_info as u16,
                     },
                 );
 
+                if let (Some(transaction), Some(all_packages), Some(installed)) = (
+                    transaction.lock().as_ref(),
+                    all_packages.get(),
+                    installed.get(),
+                ) {
+                    let lines = format_transaction(
+                        transaction,
+                        all_packages,
+                        &installed.read(),
+                        &keymap,
+                        &theme,
+                    );
+                    let height = (lines.len() as u16 + 2).min(size.height - 4);
+                    let area = Rect {
+                        x: size.width / 6,
+                        y: (size.height - height) / 2,
+                        width: size.width - size.width / 3,
+                        height,
+                    };
+                    let popup = Paragraph::new(lines).style(theme.popup).block(
+                        Block::default()
+                            .title(Span::styled(
+                                transaction.operation.title(),
+                                theme.popup_title,
+                            ))
+                            .title_alignment(Alignment::Center)
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Rounded),
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_widget(popup, area);
+                }
+
+                if let (Some(review), Some(all_packages)) = (
+                    review
+                        .lock()
+                        .as_ref()
+                        .filter(|_| mode.load(Ordering::SeqCst) == Mode::Review),
+                    all_packages.get(),
+                ) {
+                    let area = Rect {
+                        x: size.width / 12,
+                        y: 1,
+                        width: size.width - size.width / 6,
+                        height: size.height - 2,
+                    };
+                    let block = Block::default()
+                        .title(Span::styled(" Review PKGBUILDs ", theme.popup_title))
+                        .title_alignment(Alignment::Center)
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .style(theme.popup);
+                    f.render_widget(Clear, area);
+                    f.render_widget(block, area);
+
+                    let width = area.width.saturating_sub(4);
+                    let hint = if review.is_done() {
+                        keymap
+                            .hint(
+                                Mode::Review,
+                                &[
+                                    (Action::Confirm, "mark them as reviewed and continue"),
+                                    (Action::Cancel, "cancel"),
+                                ],
+                            )
+                            .map(|hint| Line::styled(format!("Press {hint}"), theme.install_hint))
+                            .unwrap_or_default()
+                    } else {
+                        match keymap.hint(Mode::Review, &[(Action::Cancel, "cancel")]) {
+                            Some(hint) => Line::styled(
+                                format!("Fetching PKGBUILDs, press {hint}"),
+                                theme.loading,
+                            ),
+                            None => Line::styled("Fetching PKGBUILDs...", theme.loading),
+                        }
+                    };
+                    f.render_widget(
+                        Paragraph::new(hint),
+                        Rect {
+                            x: area.x + 2,
+                            y: area.y + 1,
+                            width,
+                            height: 1,
+                        },
+                    );
+                    f.render_widget(
+                        Paragraph::new(format_review(review, all_packages, &theme))
+                            .style(theme.popup)
+                            .scroll((review.scroll as u16, 0)),
+                        Rect {
+                            x: area.x + 2,
+                            y: area.y + 3,
+                            width,
+                            height: review_height(size) as u16,
+                        },
+                    );
+                }
+
                 unsafe {
                     malloc_trim(0);
                 }
@@ -352,10 +838,21 @@ async fn main() -> Result<(), io::Error> {
                     ))?;
                     terminal.show_cursor()?;
                 }
-                Mode::Select => {
+                Mode::Select | Mode::Confirm | Mode::Tree | Mode::Pkgbuild | Mode::Review => {
                     terminal.set_cursor_position((2, line as u16 + 4))?;
                     terminal.hide_cursor()?;
                 }
+                Mode::Files => {
+                    let filter_len = files
+                        .lock()
+                        .as_ref()
+                        .map_or(0, |files| files.filter.chars().count());
+                    terminal.set_cursor_position((
+                        (split + 10 + filter_len as u16).min(size.width.saturating_sub(3)),
+                        7,
+                    ))?;
+                    terminal.show_cursor()?;
+                }
             }
         }
 
@@ -364,6 +861,10 @@ async fn main() -> Result<(), io::Error> {
         }
 
         let e = event::read()?;
+        // The helper to hand the terminal over to, along with what to print before running it.
+        let mut handoff = None;
+        // The PKGBUILDs reviewed before the handoff, stored once the helper built them.
+        let mut reviewed: Vec<Reviewed> = Vec::new();
 
         match e {
             Event::Resize(..) => redraw.store(true, Ordering::SeqCst),
@@ -372,99 +873,677 @@ async fn main() -> Result<(), io::Error> {
                 let s = s.trim();
                 query.insert_str(insert_pos as usize, s);
                 insert_pos += s.len() as u16;
+                current = 0;
+                live_search(Search::new(&query, descriptions, fuzzy));
                 redraw.store(true, Ordering::SeqCst);
             }
-            Event::Mouse(m) => match m.kind {
-                MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
-                    if mode.load(Ordering::SeqCst) == Mode::Select =>
-                {
-                    use MouseEventKind as Kind;
-                    if within_list(size, m.row, m.column) {
-                        let result_count = shown_len();
-
-                        match m.kind {
-                            Kind::ScrollUp => {
-                                if current > 0 {
-                                    current -= 1;
-                                } else {
-                                    current = result_count - 1;
+            Event::Mouse(m)
+                if matches!(mode.load(Ordering::SeqCst), Mode::Insert | Mode::Select) =>
+            {
+                match m.kind {
+                    MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                        if mode.load(Ordering::SeqCst) == Mode::Select =>
+                    {
+                        use MouseEventKind as Kind;
+                        if within_list(size, split, m.row, m.column) {
+                            let result_count = shown_len();
+
+                            match m.kind {
+                                Kind::ScrollUp => {
+                                    if current > 0 {
+                                        current -= 1;
+                                    } else {
+                                        current = result_count - 1;
+                                    }
                                 }
+                                Kind::ScrollDown => {
+                                    if result_count > 1 && current < result_count - 1 {
+                                        current += 1;
+                                    } else {
+                                        current = 0;
+                                    }
+                                }
+                                _ => unreachable!(),
                             }
-                            Kind::ScrollDown => {
-                                if result_count > 1 && current < result_count - 1 {
-                                    current += 1;
-                                } else {
-                                    current = 0;
+                            info_scroll = 0;
+                            info.lock().clear();
+                            redraw.store(true, Ordering::SeqCst);
+                        } else if within_info(size, split, m.row, m.column)
+                            && !info.lock().is_empty()
+                        {
+                            match m.kind {
+                                Kind::ScrollUp => {
+                                    info_scroll = info_scroll.saturating_sub(1);
+                                }
+                                Kind::ScrollDown => {
+                                    info_scroll += 1;
                                 }
+                                _ => unreachable!(),
                             }
-                            _ => unreachable!(),
+                            redraw.store(true, Ordering::SeqCst);
                         }
-                        info_scroll = 0;
-                        info.lock().clear();
-                        redraw.store(true, Ordering::SeqCst);
-                    } else if within_info(size, m.row, m.column) && !info.lock().is_empty() {
-                        match m.kind {
-                            Kind::ScrollUp => {
-                                info_scroll = info_scroll.saturating_sub(1);
+                    }
+                    MouseEventKind::Down(b @ (MouseButton::Left | MouseButton::Right)) => {
+                        if b == MouseButton::Left {
+                            if m.row <= 2 {
+                                if mode.swap(Mode::Insert, Ordering::SeqCst) == Mode::Select {
+                                    redraw.store(true, Ordering::SeqCst);
+                                }
+                                continue;
+                            } else if shown_len() > 0
+                                && mode.swap(Mode::Select, Ordering::SeqCst) == Mode::Insert
+                            {
+                                redraw.store(true, Ordering::SeqCst);
                             }
-                            Kind::ScrollDown => {
-                                info_scroll += 1;
+                        }
+
+                        if within_list(size, split, m.row, m.column) {
+                            let page = current / per_page;
+                            let page_start = page * per_page;
+
+                            let clicked = page_start + m.row as usize - 4;
+                            if clicked < shown_len() {
+                                if b == MouseButton::Right {
+                                    let real = real_idx(clicked);
+                                    if selected.contains(&real) {
+                                        selected.remove(&real);
+                                    } else {
+                                        selected.insert(real);
+                                    }
+                                }
+
+                                if clicked != current {
+                                    current = clicked;
+                                    info_scroll = 0;
+                                    info.lock().clear();
+                                }
+
+                                redraw.store(true, Ordering::SeqCst);
                             }
-                            _ => unreachable!(),
                         }
+                    }
+                    _ => continue,
+                }
+            }
+            Event::Key(k) => match keymap.get(mode.load(Ordering::SeqCst), k) {
+                Some(Action::Quit) => {
+                    disable_raw_mode()?;
+                    stream_exit!(terminal.backend_mut())?;
+
+                    if let Some(search_task) = search_task.take() {
+                        search_task.abort();
+                    }
+
+                    return Ok(());
+                }
+                Some(Action::SwitchMode) if mode.load(Ordering::SeqCst) == Mode::Insert => {
+                    if shown_len() > 0 {
+                        current = 0;
                         redraw.store(true, Ordering::SeqCst);
+                        mode.store(Mode::Select, Ordering::SeqCst);
                     }
                 }
-                MouseEventKind::Down(b @ (MouseButton::Left | MouseButton::Right)) => {
-                    if b == MouseButton::Left {
-                        if m.row <= 2 {
-                            if mode.swap(Mode::Insert, Ordering::SeqCst) == Mode::Select {
-                                redraw.store(true, Ordering::SeqCst);
-                            }
-                            continue;
-                        } else if shown_len() > 0
-                            && mode.swap(Mode::Select, Ordering::SeqCst) == Mode::Insert
-                        {
-                            redraw.store(true, Ordering::SeqCst);
-                        }
+                Some(Action::SwitchMode | Action::Insert) => {
+                    insert_pos = query.len() as u16;
+                    redraw.store(true, Ordering::SeqCst);
+                    mode.store(Mode::Insert, Ordering::SeqCst);
+                }
+                Some(Action::Search) => {
+                    if all_packages.get().is_none() {
+                        continue;
                     }
 
-                    if within_list(size, m.row, m.column) {
-                        let page = current / per_page;
-                        let page_start = page * per_page;
+                    let search = Search::new(&query, descriptions, fuzzy);
+                    info.lock().clear();
+                    current = 0;
+
+                    // Wait for the live search of the query instead of running it again, as
+                    // `owns:` queries read every files database.
+                    let id = searcher
+                        .pending(&search)
+                        .unwrap_or_else(|| live_search(search.clone()));
+                    select_when_done.store(id, Ordering::SeqCst);
+
+                    // The live search may be done already, in which case it did not select.
+                    if searcher.shows(&search)
+                        && select_when_done
+                            .compare_exchange(id, 0, Ordering::SeqCst, Ordering::SeqCst)
+                            .is_ok()
+                    {
+                        if shown_len() > 0 {
+                            mode.store(Mode::Select, Ordering::SeqCst);
+                        } else {
+                            error_msg.store(Message::NoResults, Ordering::SeqCst);
+                        }
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::DeleteWord) => {
+                    let boundary = last_word_end(query.as_bytes(), insert_pos);
+                    query = query[..boundary].to_string() + &query[insert_pos as usize..];
+                    insert_pos = boundary as u16;
+                    current = 0;
+                    live_search(Search::new(&query, descriptions, fuzzy));
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::ToggleDescriptions) => {
+                    descriptions = !descriptions;
+                    current = 0;
+                    live_search(Search::new(&query, descriptions, fuzzy));
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::ToggleFuzzy) => {
+                    fuzzy = !fuzzy;
+                    current = 0;
+                    live_search(Search::new(&query, descriptions, fuzzy));
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::MoveUp) if mode.load(Ordering::SeqCst) == Mode::Tree => {
+                    if let Some(tree) = tree.lock().as_mut() {
+                        tree.move_up();
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::MoveDown) if mode.load(Ordering::SeqCst) == Mode::Tree => {
+                    if let Some(tree) = tree.lock().as_mut() {
+                        tree.move_down();
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(
+                    action @ (Action::InfoUp
+                    | Action::InfoDown
+                    | Action::PageBack
+                    | Action::PageForward),
+                ) if matches!(
+                    mode.load(Ordering::SeqCst),
+                    Mode::Files | Mode::Pkgbuild | Mode::Review
+                ) =>
+                {
+                    let height = match mode.load(Ordering::SeqCst) {
+                        Mode::Review => review_height(size),
+                        _ => pane_height(size),
+                    };
+                    let rows = match action {
+                        Action::InfoUp | Action::InfoDown => 1,
+                        _ => height,
+                    };
+                    let up = matches!(action, Action::InfoUp | Action::PageBack);
 
-                        let clicked = page_start + m.row as usize - 4;
-                        if clicked < shown_len() {
-                            if b == MouseButton::Right {
-                                let real = real_idx(clicked);
-                                if selected.contains(&real) {
-                                    selected.remove(&real);
+                    match mode.load(Ordering::SeqCst) {
+                        Mode::Files => {
+                            if let Some(files) = files.lock().as_mut() {
+                                if up {
+                                    files.scroll_up(rows);
                                 } else {
-                                    selected.insert(real);
+                                    files.scroll_down(rows, height);
                                 }
                             }
-
-                            if clicked != current {
-                                current = clicked;
-                                info_scroll = 0;
-                                info.lock().clear();
+                        }
+                        Mode::Review => {
+                            if let Some(review) = review.lock().as_mut() {
+                                if up {
+                                    review.scroll_up(rows);
+                                } else {
+                                    let lines =
+                                        format_review(review, all_packages.get().unwrap(), &theme)
+                                            .len();
+                                    review.scroll_down(rows, height, lines);
+                                }
                             }
+                        }
+                        _ => {
+                            if let Some(pkgbuild) = pkgbuild.lock().as_mut() {
+                                if up {
+                                    pkgbuild.scroll_up(rows);
+                                } else {
+                                    pkgbuild.scroll_down(rows, height);
+                                }
+                            }
+                        }
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::Pkgbuild) => {
+                    let (Some(_), Some(installed)) = (all_packages.get(), installed.get()) else {
+                        continue;
+                    };
+
+                    let index = real_idx(current);
+                    *pkgbuild.lock() = Some(PkgbuildView::new(index));
+                    mode.store(Mode::Pkgbuild, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
 
+                    let command = command.clone();
+                    let all_packages = all_packages.clone();
+                    let installed = installed.read().clone();
+                    let pkgbuild = pkgbuild.clone();
+                    let redraw = redraw.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let fetched = fetch_pkgbuild(
+                            Some(&command),
+                            all_packages.get().unwrap(),
+                            &installed,
+                            index,
+                        );
+                        // The view may have been closed or opened on another package meanwhile.
+                        if let Some(view) =
+                            pkgbuild.lock().as_mut().filter(|view| view.index == index)
+                        {
+                            view.fetched = fetched;
                             redraw.store(true, Ordering::SeqCst);
                         }
+                    });
+                }
+                Some(Action::Files) => {
+                    let (Some(_), Some(installed)) = (all_packages.get(), installed.get()) else {
+                        continue;
+                    };
+
+                    let index = real_idx(current);
+                    *files.lock() = Some(FileList::new(index));
+                    mode.store(Mode::Files, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+
+                    let all_packages = all_packages.clone();
+                    let installed = installed.read().clone();
+                    let files = files.clone();
+                    let redraw = redraw.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let listing = read_files(all_packages.get().unwrap(), &installed, index);
+                        // The list may have been closed or opened on another package meanwhile.
+                        if let Some(files) =
+                            files.lock().as_mut().filter(|files| files.index == index)
+                        {
+                            files.listing = listing;
+                            redraw.store(true, Ordering::SeqCst);
+                        }
+                    });
+                }
+                Some(action @ (Action::Dependencies | Action::Dependents)) => {
+                    let Some(packages) = all_packages.get() else {
+                        continue;
+                    };
+
+                    let direction = match action {
+                        Action::Dependents => Direction::Dependents,
+                        _ => Direction::Dependencies,
+                    };
+                    let index = real_idx(current);
+                    let new =
+                        Tree::new(direction, index, packages, &installed.get().unwrap().read());
+                    // The dependencies of AUR packages have to be fetched.
+                    let fetch = new.root.children.is_none();
+                    *tree.lock() = Some(new);
+                    jumped_from = None;
+                    if fetch {
+                        fetch_dependencies(Vec::new(), index);
                     }
+
+                    mode.store(Mode::Tree, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
                 }
-                _ => continue,
-            },
-            Event::Key(k) => match mode.load(Ordering::SeqCst) {
-                Mode::Insert => match k.code {
-                    KeyCode::Esc => {
-                        if shown_len() > 0 {
+                Some(Action::Expand) => {
+                    let fetch = tree.lock().as_mut().and_then(|tree| {
+                        tree.expand(
+                            all_packages.get().unwrap(),
+                            &installed.get().unwrap().read(),
+                        )
+                    });
+                    if let Some((path, index)) = fetch {
+                        fetch_dependencies(path, index);
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::Collapse) => {
+                    if let Some(tree) = tree.lock().as_mut() {
+                        tree.collapse();
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::Jump) => {
+                    let Some(index) = tree.lock().as_ref().and_then(|tree| tree.current()?.index)
+                    else {
+                        continue;
+                    };
+
+                    let position = match shown.read().get_vec() {
+                        Some(results) => results.iter().position(|&i| i == index),
+                        None => Some(index),
+                    };
+                    info_scroll = 0;
+                    info.lock().clear();
+
+                    if let Some(position) = position {
+                        *tree.lock() = None;
+                        mode.store(Mode::Select, Ordering::SeqCst);
+                        current = position;
+                        redraw.store(true, Ordering::SeqCst);
+                        continue;
+                    }
+
+                    // Search for the package itself, staying in the tree until the search selects
+                    // it so that it can still be cancelled.
+                    // Jumping again before the search is done keeps what was shown before the
+                    // first jump.
+                    if jumped_from.is_none() {
+                        let previous_view = view.load(Ordering::SeqCst);
+                        jumped_from = Some(Previous {
+                            query: query.clone(),
+                            insert_pos,
+                            fuzzy,
+                            current,
+                            view: previous_view,
+                            // Listed views cannot be searched again.
+                            results: (previous_view != View::Search)
+                                .then(|| shown.read().get_vec().cloned().unwrap_or_default()),
+                        });
+                    }
+                    query = format!(
+                        "name:^{}$",
+                        regex::escape(&all_packages.get().unwrap()[index])
+                    );
+                    insert_pos = query.len() as u16;
+                    fuzzy = false;
+                    current = 0;
+
+                    let id = live_search(Search::new(&query, descriptions, fuzzy));
+                    select_when_done.store(id, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::MoveUp) => {
+                    if current > 0 {
+                        current -= 1;
+                    } else {
+                        current = shown_len() - 1;
+                    }
+                    info.lock().clear();
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::MoveDown) => {
+                    let result_count = shown_len();
+
+                    if result_count > 1 && current < result_count - 1 {
+                        current += 1;
+                    } else {
+                        current = 0;
+                    }
+                    info.lock().clear();
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::InfoUp) => {
+                    if info_scroll > 0 {
+                        info_scroll -= 1;
+                        redraw.store(true, Ordering::SeqCst);
+                    }
+                }
+                Some(Action::InfoDown) => {
+                    if !info.lock().is_empty() {
+                        info_scroll += 1;
+                        redraw.store(true, Ordering::SeqCst);
+                    }
+                }
+                Some(Action::PageBack) => {
+                    let result_count = shown_len() - 1;
+                    if result_count > per_page {
+                        if current >= per_page {
+                            current -= per_page;
+                        } else if current.is_multiple_of(per_page) {
+                            current = result_count / per_page * per_page;
+                        } else {
+                            current = current / per_page * per_page;
+                        }
+                        info.lock().clear();
+                        redraw.store(true, Ordering::SeqCst);
+                    }
+                }
+                Some(Action::PageForward) => {
+                    let shown_len = shown_len();
+
+                    if shown_len > per_page {
+                        if current == shown_len - 1 {
                             current = 0;
+                        } else if current + per_page > shown_len - 1 {
+                            current = shown_len - 1;
+                        } else {
+                            current += per_page;
+                        }
+                        info.lock().clear();
+                        redraw.store(true, Ordering::SeqCst);
+                    }
+                }
+                Some(Action::GoToStart) => {
+                    if current != 0 {
+                        info.lock().clear();
+                        current = 0;
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::GoToEnd) => {
+                    if current != shown_len() - 1 {
+                        info.lock().clear();
+                        current = shown_len() - 1;
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::ToggleSelect) => {
+                    let real_current = real_idx(current);
+                    if selected.contains(&real_current) {
+                        selected.remove(&real_current);
+                    } else {
+                        selected.insert(real_current);
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::ClearSelection) => {
+                    selected.clear();
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(action @ (Action::Upgrades | Action::Orphans)) => {
+                    let Some(installed) = installed.get() else {
+                        continue;
+                    };
+
+                    current = 0;
+                    info_scroll = 0;
+                    info.lock().clear();
+
+                    let target = match action {
+                        Action::Orphans => View::Orphans,
+                        _ => View::Upgrades,
+                    };
+                    if view.load(Ordering::SeqCst) == target {
+                        live_search(Search::new(&query, descriptions, fuzzy));
+                    } else {
+                        let listed = match target {
+                            View::Orphans => installed.read().orphans(),
+                            _ => installed.read().outdated(),
+                        };
+                        if listed.is_empty() {
+                            error_msg.store(target.empty_message(), Ordering::SeqCst);
+                            mode.store(Mode::Insert, Ordering::SeqCst);
+                        }
+                        searcher.show(listed, &shown);
+                        *highlight.lock() = None;
+                        view.store(target, Ordering::SeqCst);
+                    }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::RemoveOrphans) => {
+                    let Some(installed) = installed.get() else {
+                        continue;
+                    };
+
+                    let orphans = installed.read().orphans();
+                    if orphans.is_empty() {
+                        continue;
+                    }
+
+                    *transaction.lock() = Some(Transaction::new(Operation::RemoveOrphans, orphans));
+                    mode.store(Mode::Confirm, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::SystemUpgrade) => {
+                    let mut cmd = std::process::Command::new(&command);
+                    cmd.arg("-Syu").args(&flags);
+                    let outdated = installed
+                        .get()
+                        .map(|installed| installed.read().outdated())
+                        .unwrap_or_default();
+                    handoff = review_first(
+                        Transaction::new(Operation::Upgrade, outdated),
+                        (cmd, b"Upgrading the system.\n".to_vec()),
+                    );
+                }
+                Some(Action::ToggleReason) => {
+                    let installed = installed.get().unwrap().read();
+                    let targets = if selected.is_empty() {
+                        vec![real_idx(current)]
+                    } else {
+                        selected.iter().copied().collect()
+                    };
+                    let targets = targets
+                        .into_iter()
+                        .filter_map(|i| installed.get(i))
+                        .collect::<Vec<_>>();
+
+                    if targets.is_empty() {
+                        continue;
+                    }
+
+                    // A mix of both is marked as explicitly installed, so that nothing becomes an
+                    // orphan by accident.
+                    let (flag, reason) = if targets
+                        .iter()
+                        .all(|package| package.reason == InstallReason::Explicit)
+                    {
+                        ("--asdeps", "dependencies")
+                    } else {
+                        ("--asexplicit", "explicitly installed")
+                    };
+                    let names = targets
+                        .iter()
+                        .map(|package| &*package.name)
+                        .collect::<Vec<_>>();
+
+                    let mut cmd = std::process::Command::new(&command);
+                    cmd.arg("-D").arg(flag).args(&names);
+                    let message = format!("Marking {} as {reason}.\n", names.join(", "));
+                    handoff = Some((cmd, message.into_bytes()));
+                }
+                Some(Action::Remove) => {
+                    let installed = installed.get().unwrap().read();
+                    let targets = if selected.is_empty() {
+                        vec![real_idx(current)]
+                    } else {
+                        selected.iter().copied().collect()
+                    };
+                    let targets = targets
+                        .into_iter()
+                        .filter(|i| installed.contains(i))
+                        .collect::<Vec<_>>();
+
+                    if targets.is_empty() {
+                        continue;
+                    }
+
+                    let operation = match view.load(Ordering::SeqCst) {
+                        View::Orphans => Operation::RemoveOrphans,
+                        View::Search | View::Upgrades => Operation::Remove,
+                    };
+                    *transaction.lock() = Some(Transaction::new(operation, targets));
+                    mode.store(Mode::Confirm, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                Some(Action::Install) => {
+                    let operation = match view.load(Ordering::SeqCst) {
+                        View::Search | View::Orphans => Operation::Install,
+                        View::Upgrades => Operation::Upgrade,
+                    };
+                    let targets = if selected.is_empty() {
+                        vec![real_idx(current)]
+                    } else {
+                        selected.iter().copied().collect::<Vec<_>>()
+                    };
+                    *transaction.lock() = Some(Transaction::new(operation, targets.clone()));
+                    mode.store(Mode::Confirm, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+
+                    let all_packages = all_packages.clone();
+                    let installed = installed.get().unwrap().read().clone();
+                    let transaction = transaction.clone();
+                    let redraw = redraw.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let dependencies =
+                            resolve(all_packages.get().unwrap(), &installed, &targets);
+                        // The transaction may have been cancelled or replaced in the meantime.
+                        if let Some(transaction) = transaction
+                            .lock()
+                            .as_mut()
+                            .filter(|transaction| transaction.targets == targets)
+                        {
+                            transaction.dependencies = Some(dependencies);
                             redraw.store(true, Ordering::SeqCst);
-                            mode.store(Mode::Select, Ordering::SeqCst);
+                        }
+                    });
+                }
+                Some(Action::Confirm) if mode.load(Ordering::SeqCst) == Mode::Review => {
+                    // Nothing gets built before every PKGBUILD could be looked at.
+                    let Some(done) = review.lock().take_if(|review| review.is_done()) else {
+                        continue;
+                    };
+                    mode.store(Mode::Select, Ordering::SeqCst);
+
+                    reviewed = done.reviewed(all_packages.get().unwrap());
+                    handoff = Some(done.handoff);
+                }
+                Some(Action::Confirm) => {
+                    let Some(pending) = transaction.lock().take() else {
+                        continue;
+                    };
+                    let packages = all_packages.get().unwrap();
+                    mode.store(Mode::Select, Ordering::SeqCst);
+                    let pending_handoff = (
+                        pending.command(&command, &flags, packages),
+                        pending.message(packages),
+                    );
+                    handoff = if pending.operation.removes() {
+                        Some(pending_handoff)
+                    } else {
+                        review_first(pending, pending_handoff)
+                    };
+                }
+                Some(Action::Cancel) => {
+                    if let Some(previous) = jumped_from
+                        .take()
+                        .filter(|_| mode.load(Ordering::SeqCst) == Mode::Tree)
+                    {
+                        select_when_done.store(0, Ordering::SeqCst);
+                        query = previous.query;
+                        insert_pos = previous.insert_pos;
+                        fuzzy = previous.fuzzy;
+                        current = previous.current;
+                        match previous.results {
+                            Some(results) => {
+                                searcher.show(results, &shown);
+                                *highlight.lock() = None;
+                                view.store(previous.view, Ordering::SeqCst);
+                            }
+                            None => {
+                                live_search(Search::new(&query, descriptions, fuzzy));
+                            }
                         }
                     }
+
+                    *transaction.lock() = None;
+                    *tree.lock() = None;
+                    *files.lock() = None;
+                    *pkgbuild.lock() = None;
+                    *review.lock() = None;
+                    mode.store(Mode::Select, Ordering::SeqCst);
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                None if mode.load(Ordering::SeqCst) == Mode::Insert => match k.code {
                     KeyCode::Left => {
                         if k.modifiers.contains(KeyModifiers::CONTROL) {
                             let boundary = last_word_end(query.as_bytes(), insert_pos);
@@ -499,250 +1578,145 @@ async fn main() -> Result<(), io::Error> {
                         if insert_pos != 0 {
                             query.remove(insert_pos as usize - 1);
                             insert_pos -= 1;
+                            current = 0;
+                            live_search(Search::new(&query, descriptions, fuzzy));
                             redraw.store(true, Ordering::SeqCst);
                         }
                     }
-                    KeyCode::Char(c) => match c {
-                        'c' if k.modifiers == KeyModifiers::CONTROL => {
-                            disable_raw_mode()?;
-                            stream_exit!(terminal.backend_mut())?;
-
-                            if let Some(search_task) = search_task.take() {
-                                search_task.abort();
-                            }
-
-                            return Ok(());
-                        }
-                        'w' | 'h' if k.modifiers == KeyModifiers::CONTROL => {
-                            let boundary = last_word_end(query.as_bytes(), insert_pos);
-                            query = query[..boundary].to_string() + &query[insert_pos as usize..];
-                            insert_pos = boundary as u16;
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                        _ => {
-                            query.insert(insert_pos as usize, c);
-                            insert_pos += 1;
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                    },
-                    KeyCode::Enter => {
-                        if all_packages.get().is_none() {
-                            continue;
-                        }
-
-                        search(&query, all_packages.get().unwrap(), shown.clone());
-                        info.lock().clear();
+                    KeyCode::Char(c) => {
+                        query.insert(insert_pos as usize, c);
+                        insert_pos += 1;
                         current = 0;
-
-                        if shown_len() > 0 {
-                            mode.store(Mode::Select, Ordering::SeqCst);
-                        } else {
-                            error_msg.store(Message::NoResults, Ordering::SeqCst);
-                        }
+                        live_search(Search::new(&query, descriptions, fuzzy));
                         redraw.store(true, Ordering::SeqCst);
                     }
                     _ => redraw.store(true, Ordering::SeqCst),
                 },
-                Mode::Select => match k.code {
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if k.modifiers == KeyModifiers::CONTROL {
-                            if info_scroll > 0 {
-                                info_scroll -= 1;
-                                redraw.store(true, Ordering::SeqCst);
-                            }
-                        } else {
-                            if current > 0 {
-                                current -= 1;
-                            } else {
-                                current = shown_len() - 1;
-                            }
-                            info.lock().clear();
-                            redraw.store(true, Ordering::SeqCst);
+                None if mode.load(Ordering::SeqCst) == Mode::Files => {
+                    if let Some(files) = files.lock().as_mut() {
+                        match k.code {
+                            KeyCode::Backspace => files.pop(),
+                            KeyCode::Char(c) => files.push(c),
+                            _ => (),
                         }
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if k.modifiers == KeyModifiers::CONTROL {
-                            if !info.lock().is_empty() {
-                                info_scroll += 1;
-                                redraw.store(true, Ordering::SeqCst);
-                            }
-                        } else {
-                            let result_count = shown_len();
-
-                            if result_count > 1 && current < result_count - 1 {
-                                current += 1;
-                            } else {
-                                current = 0;
-                            }
-                            info.lock().clear();
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                    }
-                    KeyCode::Esc => {
-                        insert_pos = query.len() as u16;
-                        redraw.store(true, Ordering::SeqCst);
-                        mode.store(Mode::Insert, Ordering::SeqCst);
-                    }
-                    KeyCode::Left | KeyCode::PageUp | KeyCode::Char('h') => {
-                        let result_count = shown_len() - 1;
-                        if result_count > per_page {
-                            if current >= per_page {
-                                current -= per_page;
-                            } else if current.is_multiple_of(per_page) {
-                                current = result_count / per_page * per_page;
-                            } else {
-                                current = current / per_page * per_page;
-                            }
-                            info.lock().clear();
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                    }
-                    KeyCode::Right | KeyCode::PageDown | KeyCode::Char('l') => {
-                        let shown_len = shown_len();
-
-                        if shown_len > per_page {
-                            if current == shown_len - 1 {
-                                current = 0;
-                            } else if current + per_page > shown_len - 1 {
-                                current = shown_len - 1;
-                            } else {
-                                current += per_page;
-                            }
-                            info.lock().clear();
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                    }
-                    KeyCode::Home | KeyCode::Char('g') if current != 0 => {
-                        info.lock().clear();
-                        current = 0;
-                        redraw.store(true, Ordering::SeqCst);
-                    }
-                    KeyCode::End | KeyCode::Char('G') if current != shown_len() - 1 => {
-                        info.lock().clear();
-                        current = shown_len() - 1;
-                        redraw.store(true, Ordering::SeqCst);
-                    }
-                    KeyCode::Char(c) => match c {
-                        ' ' => {
-                            let real_current = real_idx(current);
-                            if selected.contains(&real_current) {
-                                selected.remove(&real_current);
-                            } else {
-                                selected.insert(real_current);
-                            }
-                            redraw.store(true, Ordering::SeqCst);
-                        }
-                        'i' | '/' => {
-                            insert_pos = query.len() as u16;
-                            redraw.store(true, Ordering::SeqCst);
-                            mode.store(Mode::Insert, Ordering::SeqCst);
-                        }
-                        'q' => {
-                            disable_raw_mode()?;
-                            stream_exit!(terminal.backend_mut())?;
-
-                            return Ok(());
-                        }
-                        'c' if k.modifiers.contains(KeyModifiers::CONTROL) => {
-                            disable_raw_mode()?;
-                            stream_exit!(terminal.backend_mut())?;
-
-                            return Ok(());
-                        }
-                        'R' => {
-                            let mut has_any = false;
-                            let mut cmd = std::process::Command::new(&command);
-                            cmd.arg("-R");
-                            let stdout = io::stdout().lock();
-                            let mut writer = BufWriter::new(stdout);
-                            let _ = writer.write_all(b"Removing ");
-
-                            if selected.is_empty()
-                                && installed.get().unwrap().contains(&real_idx(current))
-                            {
-                                let package = &all_packages.get().unwrap()[real_idx(current)];
-                                let _ = writer.write_all(package.as_bytes());
-                                cmd.arg(package);
-                                has_any = true;
-                            } else {
-                                for (idx, i) in selected.iter().enumerate() {
-                                    if installed.get().unwrap().contains(i) {
-                                        let package = &all_packages.get().unwrap()[*i];
-                                        let _ = writer.write_all(package.as_bytes());
-                                        if idx != selected.len() - 1 {
-                                            let _ = writer.write_all(b", ");
-                                        }
-                                        cmd.arg(package);
-                                        has_any = true;
-                                    }
-                                }
-                            }
-
-                            if !has_any {
-                                continue;
-                            }
+                    redraw.store(true, Ordering::SeqCst);
+                }
+                None => redraw.store(true, Ordering::SeqCst),
+            },
+            _ => continue,
+        }
 
-                            disable_raw_mode()?;
-                            stream_exit!(terminal.backend_mut())?;
-                            terminal.show_cursor()?;
-                            drop(terminal);
+        let Some((mut cmd, mut message)) = handoff else {
+            continue;
+        };
 
-                            let _ = writer.write_all(b".\n");
-                            let _ = writer.flush();
+        // parui is gone once the helper exits, so what it built is only known on the next start.
+        if !args.keep_open {
+            if let Err(err) = review::store_pending(&reviewed) {
+                message.splice(
+                    0..0,
+                    format!("Could not store the reviewed PKGBUILDs: {err}\n").into_bytes(),
+                );
+            }
+        }
 
-                            let _ = cmd.exec();
+        disable_raw_mode()?;
+        stream_exit!(terminal.backend_mut())?;
+        terminal.show_cursor()?;
 
-                            return Ok(());
-                        }
+        {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(&message);
+            let _ = stdout.flush();
+        }
 
-                        _ => redraw.store(true, Ordering::SeqCst),
-                    },
-                    KeyCode::Enter => {
-                        disable_raw_mode()?;
-                        stream_exit!(terminal.backend_mut())?;
-                        terminal.show_cursor()?;
-                        drop(terminal);
-
-                        if let Some(search_task) = search_task.take() {
-                            search_task.abort();
-                        }
+        if !args.keep_open {
+            if let Some(search_task) = search_task.take() {
+                search_task.abort();
+            }
 
-                        let mut cmd = std::process::Command::new(command);
-                        cmd.arg("-S");
+            let _ = cmd.exec();
 
-                        let stdout = io::stdout().lock();
-                        let mut writer = BufWriter::new(stdout);
-                        let _ = writer.write_all(b"Installing ");
+            return Ok(());
+        }
 
-                        if selected.is_empty() {
-                            let package = &all_packages.get().unwrap()[real_idx(current)];
-                            let _ = writer.write_all(package.as_bytes());
-                            cmd.arg(package);
-                        } else {
-                            for (idx, i) in selected.iter().enumerate() {
-                                let package = &all_packages.get().unwrap()[*i];
-                                let _ = writer.write_all(package.as_bytes());
-                                if idx != selected.len() - 1 {
-                                    let _ = writer.write_all(b", ");
-                                }
+        // The PKGBUILDs only count as reviewed once the helper built them.
+        if cmd.status().is_ok_and(|status| status.success()) {
+            if let Err(err) = review::store(&reviewed) {
+                println!("Could not store the reviewed PKGBUILDs: {err}");
+            }
+        }
 
-                                cmd.arg(package);
-                            }
-                        }
+        print!("Press ENTER to return to parui.");
+        io::stdout().flush()?;
+        io::stdin().read_line(&mut String::new())?;
 
-                        let _ = writer.write_all(b".\n");
-                        let _ = writer.flush();
+        enable_raw_mode()?;
+        stream_enter!(terminal.backend_mut())?;
+        terminal.clear()?;
 
-                        let _ = cmd.exec();
+        // The helper may have installed or removed anything, so look at the local database again
+        // and show the results of the query as they are now.
+        if let (Some(packages), Some(installed)) = (all_packages.get(), installed.get()) {
+            *installed.write() = Arc::new(check_installed(packages));
+        }
+        selected.clear();
+        info_scroll = 0;
+        info.lock().clear();
+
+        match view.load(Ordering::SeqCst) {
+            // Leaves the cursor where it was, the results are only shown once the search is done.
+            View::Search => {
+                live_search(Search::new(&query, descriptions, fuzzy));
+            }
+            listed => {
+                if let Some(installed) = installed.get() {
+                    let installed = installed.read();
+                    let results = match listed {
+                        View::Orphans => installed.orphans(),
+                        _ => installed.outdated(),
+                    };
+                    searcher.show(results, &shown);
+                }
+                error_msg.store(listed.empty_message(), Ordering::SeqCst);
 
-                        return Ok(());
-                    }
-                    _ => redraw.store(true, Ordering::SeqCst),
-                },
-            },
-            _ => continue,
+                if shown_len() == 0 {
+                    mode.store(Mode::Insert, Ordering::SeqCst);
+                }
+            }
         }
+        redraw.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fetches the PKGBUILD of the package at `index` for the review, unless the review was replaced
+/// in the meantime.
+fn review_entry(
+    packages: &Packages,
+    installed: &Installed,
+    index: usize,
+    review: &Mutex<Option<Review>>,
+    redraw: &AtomicBool,
+) {
+    // The clone cache still has the PKGBUILD of the installed version.
+    let fetched = fetch_pkgbuild(None, packages, installed, index);
+    let entry = Entry::new(index, fetched);
+    if let Some(review) = review.lock().as_mut() {
+        review.set(entry);
     }
+    redraw.store(true, Ordering::SeqCst);
+}
+
+/// The query and results shown before jumping to a package from the tree.
+struct Previous {
+    query: String,
+    insert_pos: u16,
+    fuzzy: bool,
+    current: usize,
+    view: View,
+    /// The results of a listed view, `None` when they came from searching for the query.
+    results: Option<Vec<usize>>,
 }
 
 #[inline(always)]
@@ -771,15 +1745,22 @@ fn next_word_start(bytes: &[u8], pos: u16) -> usize {
         .unwrap_or(bytes.len())
 }
 
-fn within_list(size: Size, row: u16, col: u16) -> bool {
-    col >= 1
-        && col < (size.width / 2).saturating_sub(1)
-        && row >= 4
-        && row < size.height.saturating_sub(1)
+/// The number of rows visible below the header of the file list and the PKGBUILD.
+fn pane_height(size: Size) -> usize {
+    (size.height - 11) as usize
+}
+
+/// The number of rows visible below the hint of the review popup.
+fn review_height(size: Size) -> usize {
+    (size.height - 6) as usize
+}
+
+fn within_list(size: Size, split: u16, row: u16, col: u16) -> bool {
+    col >= 1 && col < split.saturating_sub(1) && row >= 4 && row < size.height.saturating_sub(1)
 }
 
-fn within_info(size: Size, row: u16, col: u16) -> bool {
-    col > size.width / 2
+fn within_info(size: Size, split: u16, row: u16, col: u16) -> bool {
+    col > split
         && col < size.width.saturating_sub(3)
         && row >= 5
         && row < size.height.saturating_sub(2)
@@ -1,10 +1,26 @@
+use std::ops::Range;
+
 pub trait Matcher<T>
 where
     T: ?Sized,
 {
     fn matches(&self, value: T) -> bool;
+
+    /// Returns the byte ranges of `value` that were matched, in order.
+    fn ranges(&self, value: T) -> Vec<Range<usize>>;
+
+    /// Scores how well `value` matched, higher is better, or `None` if it did not match.
+    fn score(&self, value: T) -> Option<i64>
+    where
+        T: Sized,
+    {
+        self.matches(value).then_some(0)
+    }
 }
 
+/// A compiled query kept around to highlight the results it produced.
+pub type Highlighter = dyn for<'a> Matcher<&'a str> + Send + Sync;
+
 impl<T> Matcher<T> for str
 where
     T: AsRef<str>,
@@ -12,6 +28,18 @@ where
     fn matches(&self, value: T) -> bool {
         self.is_empty() || value.as_ref().contains(self)
     }
+
+    fn ranges(&self, value: T) -> Vec<Range<usize>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        value
+            .as_ref()
+            .match_indices(self)
+            .map(|(start, matched)| start..start + matched.len())
+            .collect()
+    }
 }
 
 impl<T> Matcher<T> for regex::Regex
@@ -21,4 +49,221 @@ where
     fn matches(&self, value: T) -> bool {
         self.is_match(value.as_ref())
     }
+
+    fn ranges(&self, value: T) -> Vec<Range<usize>> {
+        self.find_iter(value.as_ref())
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// A fzf-style fuzzy matcher, matching any value that contains the pattern as a subsequence.
+///
+/// The pattern is matched case-insensitively unless it contains an uppercase character.
+pub struct Fuzzy {
+    chars: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl Fuzzy {
+    const SCORE_MATCH: i64 = 16;
+    const PENALTY_GAP_START: i64 = 3;
+    const PENALTY_GAP_EXTENSION: i64 = 1;
+    const BONUS_BOUNDARY: i64 = 8;
+    const BONUS_CAMEL: i64 = 7;
+    const BONUS_CONSECUTIVE: i64 = 4;
+    const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+    pub fn new(pattern: &str) -> Self {
+        let case_sensitive = pattern.chars().any(char::is_uppercase);
+        let chars = pattern
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| if case_sensitive { c } else { fold(c) })
+            .collect();
+
+        Self {
+            chars,
+            case_sensitive,
+        }
+    }
+
+    /// Scores the best match of the pattern in `value`, higher is better.
+    pub fn score(&self, value: &str) -> Option<i64> {
+        self.best_match(value, None)
+    }
+
+    fn eq(&self, c: char, p: char) -> bool {
+        if self.case_sensitive {
+            c == p
+        } else {
+            fold(c) == p
+        }
+    }
+
+    fn best_match(&self, value: &str, mut ranges: Option<&mut Vec<Range<usize>>>) -> Option<i64> {
+        if self.chars.is_empty() {
+            return Some(0);
+        }
+
+        // Find the first window that contains the pattern...
+        let mut pi = 0;
+        let mut end = None;
+        for (i, c) in value.char_indices() {
+            if self.eq(c, self.chars[pi]) {
+                pi += 1;
+                if pi == self.chars.len() {
+                    end = Some(i + c.len_utf8());
+                    break;
+                }
+            }
+        }
+        let end = end?;
+
+        // ...then shrink it from the left by matching backwards from its end.
+        let mut pi = self.chars.len() - 1;
+        let mut start = 0;
+        for (i, c) in value[..end].char_indices().rev() {
+            if self.eq(c, self.chars[pi]) {
+                if pi == 0 {
+                    start = i;
+                    break;
+                }
+                pi -= 1;
+            }
+        }
+
+        let mut score = 0;
+        let mut prev = value[..start].chars().next_back();
+        let mut pi = 0;
+        let mut in_gap = false;
+        let mut last_matched = None;
+        for (i, c) in value[start..end].char_indices() {
+            let i = i + start;
+            if pi < self.chars.len() && self.eq(c, self.chars[pi]) {
+                let mut bonus = match prev {
+                    None => Self::BONUS_BOUNDARY,
+                    Some(prev) if !prev.is_alphanumeric() => Self::BONUS_BOUNDARY,
+                    Some(prev) if prev.is_lowercase() && c.is_uppercase() => Self::BONUS_CAMEL,
+                    Some(prev) if !prev.is_ascii_digit() && c.is_ascii_digit() => Self::BONUS_CAMEL,
+                    _ => 0,
+                };
+                if pi == 0 {
+                    bonus *= Self::BONUS_FIRST_CHAR_MULTIPLIER;
+                }
+
+                score += Self::SCORE_MATCH + bonus;
+                if last_matched == Some(i) {
+                    score += Self::BONUS_CONSECUTIVE;
+                }
+
+                if let Some(ranges) = ranges.as_deref_mut() {
+                    match ranges.last_mut() {
+                        Some(range) if range.end == i => range.end = i + c.len_utf8(),
+                        _ => ranges.push(i..i + c.len_utf8()),
+                    }
+                }
+
+                last_matched = Some(i + c.len_utf8());
+                in_gap = false;
+                pi += 1;
+            } else if in_gap {
+                score -= Self::PENALTY_GAP_EXTENSION;
+            } else {
+                score -= Self::PENALTY_GAP_START;
+                in_gap = true;
+            }
+
+            prev = Some(c);
+        }
+
+        Some(score)
+    }
+}
+
+fn fold(c: char) -> char {
+    if c.is_ascii() {
+        c.to_ascii_lowercase()
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+impl<T> Matcher<T> for Fuzzy
+where
+    T: AsRef<str>,
+{
+    fn matches(&self, value: T) -> bool {
+        Fuzzy::score(self, value.as_ref()).is_some()
+    }
+
+    fn score(&self, value: T) -> Option<i64> {
+        Fuzzy::score(self, value.as_ref())
+    }
+
+    /// Returns the byte ranges of the characters of `value` that are part of the best match.
+    fn ranges(&self, value: T) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        self.best_match(value.as_ref(), Some(&mut ranges));
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_subsequences() {
+        let fuzzy = Fuzzy::new("rg");
+        assert!(fuzzy.matches("ripgrep"));
+        assert!(fuzzy.matches("RipGrep"));
+        assert!(!fuzzy.matches("grip"));
+        assert!(!fuzzy.matches(""));
+        assert!(Fuzzy::new("").matches("anything"));
+    }
+
+    #[test]
+    fn fuzzy_smart_case() {
+        assert!(Fuzzy::new("rg").matches("RG"));
+        assert!(Fuzzy::new("RG").matches("RipGrep"));
+        assert!(!Fuzzy::new("RG").matches("ripgrep"));
+    }
+
+    #[test]
+    fn fuzzy_ranges() {
+        let fuzzy = Fuzzy::new("rgp");
+        assert_eq!(fuzzy.ranges("ripgrep"), [0..1, 3..4, 6..7]);
+
+        // The window is shrunk to the last possible start of the match.
+        assert_eq!(
+            Fuzzy::new("gr").ranges("ripgrep"),
+            [Range { start: 3, end: 5 }]
+        );
+        assert!(Fuzzy::new("xyz").ranges("ripgrep").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_scores() {
+        let fuzzy = Fuzzy::new("grep");
+        let score = |value| Fuzzy::score(&fuzzy, value).unwrap();
+
+        // Consecutive characters beat scattered ones.
+        assert!(score("grep") > score("gxrxexp"));
+        // Matches at word boundaries beat matches inside words.
+        assert!(score("rip-grep") > score("ripgrep"));
+        // Shorter gaps beat longer ones.
+        assert!(score("gxrep") > score("gxxxxrep"));
+        assert_eq!(Fuzzy::score(&fuzzy, "grip"), None);
+    }
+
+    #[test]
+    fn str_and_regex_ranges() {
+        assert_eq!("ab".ranges("abcab"), [0..2, 3..5]);
+        assert!("".ranges("abc").is_empty());
+
+        let regex = regex::Regex::new("a+").unwrap();
+        assert_eq!(regex.ranges("caaab a"), [1..4, 6..7]);
+    }
 }
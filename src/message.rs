@@ -1,12 +1,19 @@
 use bytemuck::NoUninit;
 
-#[derive(Clone, Copy, NoUninit)]
+#[derive(Clone, Copy, Debug, NoUninit)]
 #[repr(u8)]
 pub enum Message {
     TrySearch,
     ListingPackages,
     Searching,
     NoResults,
+    NoUpgrades,
+    NoOrphans,
+    InvalidSyntax,
+    InvalidRegex,
+    InvalidValue,
+    UnknownField,
+    MissingPath,
 }
 
 impl Message {
@@ -16,6 +23,28 @@ impl Message {
             Message::ListingPackages => "Listing packages...",
             Message::Searching => "Searching for packages...",
             Message::NoResults => "No results, try another query",
+            Message::NoUpgrades => "Every installed package is up to date",
+            Message::NoOrphans => "Every package installed as a dependency is still needed",
+            Message::InvalidSyntax => "Unbalanced parentheses or a missing term around an operator",
+            Message::InvalidRegex => "A term is not a valid regex",
+            Message::InvalidValue => "A field was given a value it does not accept",
+            Message::MissingPath => "Type a path or file name to find the packages owning it",
+            Message::UnknownField => {
+                "Unknown field, try name, desc, repo, installed, upgradable, maint or votes"
+            }
+        }
+    }
+
+    pub(crate) const fn title(&self) -> &'static str {
+        match self {
+            Message::InvalidSyntax
+            | Message::InvalidRegex
+            | Message::InvalidValue
+            | Message::UnknownField
+            | Message::MissingPath => " Invalid Query ",
+            Message::NoUpgrades => " Up To Date ",
+            Message::NoOrphans => " No Orphans ",
+            _ => " No Results ",
         }
     }
 }
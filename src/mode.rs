@@ -1,8 +1,67 @@
 use bytemuck::NoUninit;
 
-#[derive(Clone, Copy, NoUninit, PartialEq, Eq)]
+use crate::message::Message;
+
+#[derive(Clone, Copy, NoUninit, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Mode {
     Insert,
     Select,
+    /// A transaction is waiting to be confirmed.
+    Confirm,
+    /// The dependencies or dependents of a package are shown.
+    Tree,
+    /// The files of a package are shown and filtered.
+    Files,
+    /// The PKGBUILD of a package is shown.
+    Pkgbuild,
+    /// The PKGBUILDs of the AUR packages about to be built are compared to the reviewed ones.
+    Review,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 7] = [
+        Mode::Insert,
+        Mode::Select,
+        Mode::Confirm,
+        Mode::Tree,
+        Mode::Files,
+        Mode::Pkgbuild,
+        Mode::Review,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Mode::Insert => "Insert",
+            Mode::Select => "Select",
+            Mode::Confirm => "Confirm",
+            Mode::Tree => "Tree",
+            Mode::Files => "Files",
+            Mode::Pkgbuild => "PKGBUILD",
+            Mode::Review => "Review",
+        }
+    }
+}
+
+/// What the results list is showing.
+#[derive(Clone, Copy, NoUninit, PartialEq, Eq)]
+#[repr(u8)]
+pub enum View {
+    /// The results of the query.
+    Search,
+    /// Every installed package with a newer version available.
+    Upgrades,
+    /// Every package installed as a dependency that nothing depends on anymore.
+    Orphans,
+}
+
+impl View {
+    /// The message shown when the view is empty.
+    pub const fn empty_message(&self) -> Message {
+        match self {
+            View::Search => Message::NoResults,
+            View::Upgrades => Message::NoUpgrades,
+            View::Orphans => Message::NoOrphans,
+        }
+    }
 }
@@ -0,0 +1,325 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Index,
+};
+
+use compact_strings::FixedCompactStrings;
+use nohash_hasher::{IntMap, IntSet};
+
+use crate::{
+    aur::AurPackage,
+    db::{dep_name, InstallReason, LocalPackage, SyncPackage},
+};
+
+/// Every package known to parui.
+///
+/// Official packages come first and have their metadata stored in `sync` at the same index as
+/// their name, AUR packages follow with their metadata stored in `aur`.
+pub struct Packages {
+    names: FixedCompactStrings,
+    pub sync: Vec<SyncPackage>,
+    pub aur: Vec<AurPackage>,
+    /// The index of the package called or providing each name, see [`Packages::find`].
+    providers: HashMap<Box<str>, usize>,
+}
+
+impl Packages {
+    pub fn new(names: FixedCompactStrings, sync: Vec<SyncPackage>, aur: Vec<AurPackage>) -> Self {
+        let mut providers = HashMap::with_capacity(names.len());
+        for (i, name) in names.iter().enumerate() {
+            providers.entry(Box::from(name)).or_insert(i);
+        }
+        for (i, package) in sync.iter().enumerate() {
+            for provided in &package.provides {
+                providers.entry(Box::from(dep_name(provided))).or_insert(i);
+            }
+        }
+
+        Self {
+            names,
+            sync,
+            aur,
+            providers,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.names.iter()
+    }
+
+    pub fn get_sync(&self, index: usize) -> Option<&SyncPackage> {
+        self.sync.get(index)
+    }
+
+    pub fn get_aur(&self, index: usize) -> Option<&AurPackage> {
+        self.aur.get(index.checked_sub(self.sync.len())?)
+    }
+
+    /// Returns the repository of the package, `aur` for AUR packages.
+    pub fn repo(&self, index: usize) -> &str {
+        match self.get_sync(index) {
+            Some(package) => &package.repo,
+            None => "aur",
+        }
+    }
+
+    /// Returns the maintainer of AUR packages or the packager of official packages.
+    pub fn maintainer(&self, index: usize) -> Option<&str> {
+        match self.get_sync(index) {
+            Some(package) => Some(&package.packager),
+            None => self.get_aur(index)?.maintainer.as_deref(),
+        }
+    }
+
+    pub fn version(&self, index: usize) -> Option<&str> {
+        match self.get_sync(index) {
+            Some(package) => Some(&package.version),
+            None => Some(&self.get_aur(index)?.version),
+        }
+    }
+
+    pub fn votes(&self, index: usize) -> Option<u64> {
+        self.get_aur(index).map(|package| package.num_votes)
+    }
+
+    pub fn description(&self, index: usize) -> Option<&str> {
+        match self.get_sync(index) {
+            Some(package) => Some(&package.desc),
+            None => self.get_aur(index)?.description.as_deref(),
+        }
+    }
+
+    /// Returns the index of the package called `name`, or of an official package providing it.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.providers.get(name).copied()
+    }
+}
+
+impl Index<usize> for Packages {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.names[index]
+    }
+}
+
+/// Every package in the local database, keyed by its index in [`Packages`] where possible.
+#[derive(Default)]
+pub struct Installed {
+    pub packages: Vec<LocalPackage>,
+    by_index: IntMap<usize, usize>,
+    outdated: IntSet<usize>,
+    /// The position of the package called or providing each name, names taking precedence.
+    providers: HashMap<Box<str>, usize>,
+}
+
+impl Installed {
+    pub fn new(
+        packages: Vec<LocalPackage>,
+        by_index: IntMap<usize, usize>,
+        outdated: IntSet<usize>,
+    ) -> Self {
+        let mut providers = HashMap::with_capacity(packages.len());
+        for (pos, package) in packages.iter().enumerate() {
+            providers.insert(package.name.clone(), pos);
+        }
+        for (pos, package) in packages.iter().enumerate() {
+            for provided in &package.provides {
+                providers
+                    .entry(Box::from(dep_name(provided)))
+                    .or_insert(pos);
+            }
+        }
+
+        Self {
+            packages,
+            by_index,
+            outdated,
+            providers,
+        }
+    }
+
+    pub fn contains(&self, index: &usize) -> bool {
+        self.by_index.contains_key(index)
+    }
+
+    /// Whether a newer version of the installed package is available.
+    pub fn is_outdated(&self, index: &usize) -> bool {
+        self.outdated.contains(index)
+    }
+
+    /// Returns the indices of every outdated package, in the order they are listed.
+    pub fn outdated(&self) -> Vec<usize> {
+        let mut outdated = self.outdated.iter().copied().collect::<Vec<_>>();
+        outdated.sort_unstable();
+        outdated
+    }
+
+    pub fn get(&self, index: usize) -> Option<&LocalPackage> {
+        self.by_index
+            .get(&index)
+            .map(|&local| &self.packages[local])
+    }
+
+    /// Iterates over the installed packages that parui lists, along with their indices.
+    pub fn indexed(&self) -> impl Iterator<Item = (usize, &LocalPackage)> {
+        self.by_index
+            .iter()
+            .map(|(&index, &local)| (index, &self.packages[local]))
+    }
+
+    /// Returns the indices of the packages installed as dependencies that no installed package
+    /// depends on anymore, even optionally, like `pacman -Qdt`.
+    ///
+    /// Packages that parui does not list are left out.
+    pub fn orphans(&self) -> Vec<usize> {
+        let required = self
+            .packages
+            .iter()
+            .flat_map(|package| package.depends.iter().chain(&package.optdepends))
+            .map(|dep| dep_name(dep))
+            .collect::<HashSet<_>>();
+
+        let mut orphans = self
+            .by_index
+            .iter()
+            .filter(|&(_, &local)| {
+                let package = &self.packages[local];
+                package.reason == InstallReason::Dependency
+                    && !required.contains(&*package.name)
+                    && !package
+                        .provides
+                        .iter()
+                        .any(|provided| required.contains(dep_name(provided)))
+            })
+            .map(|(&index, _)| index)
+            .collect::<Vec<_>>();
+        orphans.sort_unstable();
+        orphans
+    }
+
+    /// Returns the installed package called `name`.
+    pub fn find(&self, name: &str) -> Option<&LocalPackage> {
+        self.providers
+            .get(name)
+            .map(|&pos| &self.packages[pos])
+            .filter(|package| *package.name == *name)
+    }
+
+    /// Whether an installed package is called `name` or provides it.
+    pub fn satisfies(&self, name: &str) -> bool {
+        self.providers.contains_key(name)
+    }
+
+    /// Returns the names of the installed packages that depend on `package`.
+    pub fn required_by(&self, package: &LocalPackage) -> Vec<&str> {
+        self.dependents(package, |dependent| &dependent.depends)
+    }
+
+    /// Returns the names of the installed packages that optionally depend on `package`.
+    pub fn optional_for(&self, package: &LocalPackage) -> Vec<&str> {
+        self.dependents(package, |dependent| &dependent.optdepends)
+    }
+
+    fn dependents<'a>(
+        &'a self,
+        package: &LocalPackage,
+        deps: impl Fn(&'a LocalPackage) -> &'a [Box<str>],
+    ) -> Vec<&'a str> {
+        let satisfies = |dep: &str| {
+            let dep = dep_name(dep);
+            *package.name == *dep
+                || package
+                    .provides
+                    .iter()
+                    .any(|provided| dep_name(provided) == dep)
+        };
+
+        let mut out = self
+            .packages
+            .iter()
+            .filter(|dependent| deps(dependent).iter().any(|dep| satisfies(dep)))
+            .map(|dependent| &*dependent.name)
+            .collect::<Vec<_>>();
+        out.sort_unstable();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(
+        name: &str,
+        reason: InstallReason,
+        depends: &[&str],
+        optdepends: &[&str],
+    ) -> LocalPackage {
+        LocalPackage {
+            name: name.into(),
+            reason,
+            depends: depends.iter().map(|&dep| dep.into()).collect(),
+            optdepends: optdepends.iter().map(|&dep| dep.into()).collect(),
+            ..LocalPackage::default()
+        }
+    }
+
+    #[test]
+    fn orphans_exclude_optional_dependencies() {
+        use InstallReason::*;
+
+        let packages = vec![
+            package(
+                "app",
+                Explicit,
+                &["lib>=1.0"],
+                &["extra: for extra features"],
+            ),
+            package("lib", Dependency, &[], &[]),
+            package("extra", Dependency, &[], &[]),
+            package("stale", Dependency, &[], &[]),
+            package("tool", Explicit, &[], &[]),
+        ];
+        let by_index = (0..packages.len()).map(|i| (i, i)).collect();
+        let installed = Installed::new(packages, by_index, IntSet::default());
+
+        assert_eq!(installed.orphans(), [3]);
+    }
+
+    #[test]
+    fn dependents() {
+        use InstallReason::*;
+
+        let packages = vec![
+            LocalPackage {
+                provides: ["sh=5.2".into()].into(),
+                ..package("bash", Dependency, &[], &[])
+            },
+            package(
+                "git",
+                Explicit,
+                &["bash", "perl>=5.14"],
+                &["tk: gitk and git gui"],
+            ),
+            package("makepkg", Explicit, &["sh"], &["bash: for scripts"]),
+            package("zsh", Explicit, &[], &["bash-completion"]),
+        ];
+        let by_index = (0..packages.len()).map(|i| (i, i)).collect();
+        let installed = Installed::new(packages, by_index, IntSet::default());
+        let bash = installed.find("bash").unwrap();
+
+        // Dependencies on what the package provides count, similar names do not.
+        assert_eq!(installed.required_by(bash), ["git", "makepkg"]);
+        assert_eq!(installed.optional_for(bash), ["makepkg"]);
+        assert!(installed
+            .required_by(installed.find("zsh").unwrap())
+            .is_empty());
+        assert!(installed.satisfies("sh"));
+        assert!(!installed.satisfies("perl"));
+    }
+}
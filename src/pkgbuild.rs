@@ -0,0 +1,414 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use tui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::{
+    aur::{self, AUR_URL},
+    packages::{Installed, Packages},
+    theme::Theme,
+};
+
+const KEYWORDS: [&str; 19] = [
+    "if", "then", "elif", "else", "fi", "for", "in", "do", "done", "while", "until", "case",
+    "esac", "function", "return", "local", "export", "select", "break",
+];
+
+/// Where a PKGBUILD was read from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The clone of the package base kept by the helper.
+    Cache,
+    Aur,
+}
+
+pub struct Pkgbuild {
+    /// The package base the PKGBUILD belongs to.
+    pub base: Box<str>,
+    pub source: Source,
+    pub text: String,
+}
+
+/// The PKGBUILD of a package as far as it is known.
+pub enum Fetched {
+    Loading,
+    /// Why there is no PKGBUILD, like the package not being in the AUR.
+    Unavailable(Box<str>),
+    Loaded(Pkgbuild),
+}
+
+/// The PKGBUILD of an AUR package, shown in place of its info.
+pub struct PkgbuildView {
+    /// The index of the package the PKGBUILD belongs to.
+    pub index: usize,
+    pub fetched: Fetched,
+    /// The first visible line.
+    pub scroll: usize,
+}
+
+impl PkgbuildView {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            fetched: Fetched::Loading,
+            scroll: 0,
+        }
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    /// Scrolls down by `rows`, stopping once the last line is visible in a pane `height` rows
+    /// high.
+    pub fn scroll_down(&mut self, rows: usize, height: usize) {
+        let lines = match &self.fetched {
+            Fetched::Loaded(pkgbuild) => pkgbuild.text.lines().count(),
+            Fetched::Loading | Fetched::Unavailable(_) => 0,
+        };
+        let last = lines.saturating_sub(height);
+        self.scroll = (self.scroll + rows).min(last.max(self.scroll));
+    }
+}
+
+/// Reads the PKGBUILD of the AUR package at `index` from the clone cache of `command`, falling
+/// back to fetching it from the AUR, so this may block on the network.
+///
+/// Without a command, the PKGBUILD is always fetched, as the clone cache is only updated once the
+/// helper builds the package again.
+pub fn fetch(
+    command: Option<&str>,
+    packages: &Packages,
+    installed: &Installed,
+    index: usize,
+) -> Fetched {
+    if packages.get_aur(index).is_none() {
+        return Fetched::Unavailable(Box::from("Only AUR packages have their PKGBUILD shown"));
+    }
+
+    let name = &packages[index];
+    let base = match installed.get(index).and_then(|local| local.base.as_deref()) {
+        Some(base) => base.to_owned(),
+        None => aur::info(AUR_URL, name).map_or_else(|| name.to_owned(), |info| info.package_base),
+    };
+
+    if let Some(text) = command
+        .and_then(|command| clone_dir(command, &base))
+        .and_then(|dir| fs::read_to_string(dir.join("PKGBUILD")).ok())
+    {
+        return Fetched::Loaded(Pkgbuild {
+            base: base.into(),
+            source: Source::Cache,
+            text,
+        });
+    }
+
+    match aur::pkgbuild(AUR_URL, &base) {
+        Some(text) => Fetched::Loaded(Pkgbuild {
+            base: base.into(),
+            source: Source::Aur,
+            text,
+        }),
+        None => Fetched::Unavailable(
+            format!("Could not fetch the PKGBUILD of {base} from the AUR").into(),
+        ),
+    }
+}
+
+/// Returns the directory `command` clones `base` into, if it is a helper parui knows.
+fn clone_dir(command: &str, base: &str) -> Option<PathBuf> {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".cache")))?;
+
+    clone_dir_in(&cache_home, command, base)
+}
+
+/// Returns the directory `command` clones `base` into when its cache is in `cache_home`.
+fn clone_dir_in(cache_home: &Path, command: &str, base: &str) -> Option<PathBuf> {
+    let helper = Path::new(command);
+    match helper.file_name()?.to_str()? {
+        "paru" => Some(cache_home.join("paru").join("clone").join(base)),
+        "yay" => Some(cache_home.join("yay").join(base)),
+        _ => None,
+    }
+}
+
+/// Highlights `text` as a shell script, line by line.
+///
+/// This only knows about comments, quotes, variables and keywords, which is enough to make a
+/// PKGBUILD easier to read.
+pub fn highlight<'line>(text: &str, theme: &Theme) -> Vec<Line<'line>> {
+    // The quote a string spanning several lines was opened with.
+    let mut quote = None;
+
+    text.lines()
+        .map(|line| {
+            let mut spans: Vec<Span<'line>> = Vec::new();
+            let mut push = |text: &str, style: Style| match spans.last_mut() {
+                Some(last) if last.style == style => last.content.to_mut().push_str(text),
+                _ => spans.push(Span::styled(text.to_owned(), style)),
+            };
+
+            let mut rest = line;
+            let mut command_start = true;
+            while !rest.is_empty() {
+                if let Some(q) = quote {
+                    let (end, closed) = closing_quote(rest, q);
+                    highlight_quoted(&rest[..end], q, theme, &mut push);
+                    rest = &rest[end..];
+                    if closed {
+                        quote = None;
+                    }
+                    command_start = false;
+                    continue;
+                }
+
+                let c = rest.chars().next().unwrap_or_default();
+                let word_start = line[..line.len() - rest.len()]
+                    .chars()
+                    .last()
+                    .is_none_or(char::is_whitespace);
+                match c {
+                    '#' if word_start => {
+                        push(rest, theme.pkgbuild_comment);
+                        rest = "";
+                    }
+                    '\'' | '"' => {
+                        push(&rest[..1], theme.pkgbuild_string);
+                        rest = &rest[1..];
+                        quote = Some(c);
+                    }
+                    '$' => {
+                        let len = variable_len(rest);
+                        push(&rest[..len], theme.pkgbuild_variable);
+                        rest = &rest[len..];
+                        command_start = false;
+                    }
+                    c if is_word(c) => {
+                        let len = rest.find(|c| !is_word(c)).unwrap_or(rest.len());
+                        let word = &rest[..len];
+                        let after = &rest[len..];
+                        let assigned = after.starts_with('=') || after.starts_with("+=");
+                        let keyword = !assigned && KEYWORDS.contains(&word);
+
+                        let style = match (command_start, assigned, keyword) {
+                            (true, true, _) => theme.pkgbuild_variable,
+                            (true, _, true) => theme.pkgbuild_keyword,
+                            _ => Style::default(),
+                        };
+                        push(word, style);
+                        rest = after;
+                        // Keywords are followed by another command, like `then` or `do`.
+                        command_start = command_start && keyword;
+                    }
+                    c => {
+                        let len = c.len_utf8();
+                        push(&rest[..len], Style::default());
+                        rest = &rest[len..];
+                        if matches!(c, ';' | '|' | '&' | '(' | '{' | '}') {
+                            command_start = true;
+                        } else if !c.is_whitespace() {
+                            command_start = false;
+                        }
+                    }
+                }
+            }
+
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Pushes the part of a string up to and including its closing quote, highlighting the
+/// variables expanded in double quotes.
+fn highlight_quoted(text: &str, quote: char, theme: &Theme, push: &mut impl FnMut(&str, Style)) {
+    if quote == '\'' {
+        push(text, theme.pkgbuild_string);
+        return;
+    }
+
+    let mut rest = text;
+    while let Some(start) = rest.find('$').filter(|&i| !rest[..i].ends_with('\\')) {
+        push(&rest[..start], theme.pkgbuild_string);
+        let len = variable_len(&rest[start..]);
+        push(&rest[start..start + len], theme.pkgbuild_variable);
+        rest = &rest[start + len..];
+    }
+    push(rest, theme.pkgbuild_string);
+}
+
+/// Returns the length of `text` up to and including the closing `quote`, and whether it was
+/// found.
+fn closing_quote(text: &str, quote: char) -> (usize, bool) {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if quote == '"' => escaped = !escaped,
+            c if c == quote && !escaped => return (i + 1, true),
+            _ => escaped = false,
+        }
+    }
+    (text.len(), false)
+}
+
+/// Returns the length of the expansion at the start of `text`, which starts with `$`.
+fn variable_len(text: &str) -> usize {
+    let rest = &text[1..];
+    if rest.starts_with('{') {
+        return rest.find('}').map_or(text.len(), |end| end + 2);
+    }
+
+    match rest.chars().next() {
+        Some(c) if c.is_ascii_digit() || "@*#?$!-".contains(c) => 2,
+        _ => 1 + rest.find(|c| !is_word(c)).unwrap_or(rest.len()),
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_strings::FixedCompactStrings;
+    use nohash_hasher::IntSet;
+
+    use super::*;
+    use crate::db::{LocalPackage, SyncPackage};
+
+    #[test]
+    fn repository_packages_are_unavailable() {
+        let packages = Packages::new(
+            ["glibc"].into_iter().collect::<FixedCompactStrings>(),
+            vec![SyncPackage {
+                name: "glibc".into(),
+                ..SyncPackage::default()
+            }],
+            Vec::new(),
+        );
+        let installed = Installed::new(
+            vec![LocalPackage {
+                name: "glibc".into(),
+                ..LocalPackage::default()
+            }],
+            [(0, 0)].into_iter().collect(),
+            IntSet::default(),
+        );
+
+        assert!(matches!(
+            fetch(None, &packages, &installed, 0),
+            Fetched::Unavailable(reason) if reason.starts_with("Only AUR packages")
+        ));
+    }
+
+    #[test]
+    fn clone_dirs() {
+        let cache = Path::new("/home/user/.cache");
+        assert_eq!(
+            clone_dir_in(cache, "paru", "parui"),
+            Some(PathBuf::from("/home/user/.cache/paru/clone/parui"))
+        );
+        assert_eq!(
+            clone_dir_in(cache, "/usr/bin/yay", "parui"),
+            Some(PathBuf::from("/home/user/.cache/yay/parui"))
+        );
+        assert_eq!(clone_dir_in(cache, "pacman", "parui"), None);
+    }
+
+    /// Highlights `text` with the default theme, returning the spans of every line.
+    fn spans(text: &str) -> Vec<Vec<(String, Style)>> {
+        highlight(text, &Theme::DEFAULT)
+            .into_iter()
+            .map(|line| {
+                line.spans
+                    .into_iter()
+                    .map(|span| (span.content.into_owned(), span.style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn span(text: &str, style: Style) -> (String, Style) {
+        (text.to_owned(), style)
+    }
+
+    #[test]
+    fn highlight_assignments_and_comments() {
+        let theme = Theme::DEFAULT;
+        let plain = Style::default();
+
+        assert_eq!(
+            spans("pkgname=parui # the name\necho a#b"),
+            [
+                vec![
+                    span("pkgname", theme.pkgbuild_variable),
+                    span("=parui ", plain),
+                    span("# the name", theme.pkgbuild_comment),
+                ],
+                vec![span("echo a#b", plain)],
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_strings() {
+        let theme = Theme::DEFAULT;
+        let plain = Style::default();
+
+        assert_eq!(
+            spans("source=(\"$pkgname-$pkgver.tar.gz\" '$literal')"),
+            [vec![
+                span("source", theme.pkgbuild_variable),
+                span("=(", plain),
+                span("\"", theme.pkgbuild_string),
+                span("$pkgname", theme.pkgbuild_variable),
+                span("-", theme.pkgbuild_string),
+                span("$pkgver", theme.pkgbuild_variable),
+                span(".tar.gz\"", theme.pkgbuild_string),
+                span(" ", plain),
+                span("'$literal'", theme.pkgbuild_string),
+                span(")", plain),
+            ]]
+        );
+
+        assert_eq!(
+            spans("pkgdesc=\"first\nsecond\""),
+            [
+                vec![
+                    span("pkgdesc", theme.pkgbuild_variable),
+                    span("=", plain),
+                    span("\"first", theme.pkgbuild_string),
+                ],
+                vec![span("second\"", theme.pkgbuild_string)],
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_keywords() {
+        let theme = Theme::DEFAULT;
+        let plain = Style::default();
+
+        assert_eq!(
+            spans("if [ -n \"$x\" ]; then echo if; fi"),
+            [vec![
+                span("if", theme.pkgbuild_keyword),
+                span(" [ -n ", plain),
+                span("\"", theme.pkgbuild_string),
+                span("$x", theme.pkgbuild_variable),
+                span("\"", theme.pkgbuild_string),
+                span(" ]; ", plain),
+                span("then", theme.pkgbuild_keyword),
+                span(" echo if; ", plain),
+                span("fi", theme.pkgbuild_keyword),
+            ]]
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use std::{iter::Peekable, ops::Range, sync::Arc, vec::IntoIter};
+
+use regex::Regex;
+
+use crate::{
+    matcher::{Fuzzy, Highlighter, Matcher},
+    message::Message,
+    packages::{Installed, Packages},
+};
+
+/// A parsed query.
+///
+/// Whitespace separated terms must all match, `|` or `OR` between terms matches either side,
+/// `!` or `NOT` before a term negates it and standalone parentheses group terms.
+///
+/// Terms may be prefixed by a field, e.g. `repo:extra`, otherwise they match against the name of
+/// packages, as well as their description if descriptions are being searched.
+pub struct Query {
+    root: Node,
+    descriptions: bool,
+    /// The name terms that are not negated, used to rank and highlight results.
+    positive: Vec<(Box<str>, Arc<Highlighter>)>,
+}
+
+enum Node {
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+    Term(Term),
+}
+
+enum Term {
+    /// A term without a field.
+    Bare(Arc<Highlighter>),
+    Name(Arc<Highlighter>),
+    Desc(Arc<Highlighter>),
+    Repo(Box<str>),
+    Installed(bool),
+    Upgradable(bool),
+    Maintainer(Arc<Highlighter>),
+    Votes(Comparison, u64),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn parse(value: &str) -> (Self, &str) {
+        use Comparison::*;
+
+        for (prefix, comparison) in [
+            ("<=", LessEqual),
+            (">=", GreaterEqual),
+            ("<", Less),
+            (">", Greater),
+            ("=", Equal),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (comparison, rest);
+            }
+        }
+
+        (Equal, value)
+    }
+
+    fn compare(self, a: u64, b: u64) -> bool {
+        use Comparison::*;
+
+        match self {
+            Less => a < b,
+            LessEqual => a <= b,
+            Equal => a == b,
+            GreaterEqual => a >= b,
+            Greater => a > b,
+        }
+    }
+}
+
+enum Token {
+    Open,
+    Close,
+    Or,
+    Not,
+    Word(String),
+    /// A word starting with a quote, which is never a field.
+    Quoted(String),
+}
+
+impl Query {
+    /// Whether appending to `query` can only ever narrow down its results, which is the case when
+    /// it only consists of plain terms.
+    pub fn is_plain(query: &str, fuzzy: bool) -> bool {
+        !query.contains([':', '!', '|', '(', ')', '"'])
+            && !query
+                .split_whitespace()
+                .any(|word| word == "OR" || word == "NOT")
+            && (fuzzy || regex::escape(query) == query)
+    }
+
+    pub fn parse(query: &str, descriptions: bool, fuzzy: bool) -> Result<Self, Message> {
+        let mut parser = Parser {
+            tokens: tokenize(query).into_iter().peekable(),
+            fuzzy,
+            negated: false,
+            positive: Vec::new(),
+        };
+
+        let root = parser.or()?;
+        if parser.tokens.next().is_some() {
+            return Err(Message::InvalidSyntax);
+        }
+
+        Ok(Self {
+            root,
+            descriptions,
+            positive: parser.positive,
+        })
+    }
+
+    pub fn matches(&self, index: usize, packages: &Packages, installed: &Installed) -> bool {
+        self.root
+            .matches(index, packages, installed, self.descriptions)
+    }
+
+    /// Returns the key to sort results by, highest first.
+    ///
+    /// Exact name matches come first, followed by packages whose name matched and then the
+    /// packages that only matched by description.
+    pub fn rank(&self, index: usize, packages: &Packages) -> (bool, bool, i64) {
+        let name = &packages[index];
+
+        let exact = self
+            .positive
+            .iter()
+            .any(|(pattern, _)| name.eq_ignore_ascii_case(pattern));
+
+        let name_score = self
+            .positive
+            .iter()
+            .filter_map(|(_, matcher)| matcher.score(name))
+            .reduce(|a, b| a + b);
+
+        match name_score {
+            Some(score) => (exact, true, score),
+            None => {
+                let desc = packages.description(index).unwrap_or_default();
+                let score = self
+                    .positive
+                    .iter()
+                    .filter_map(|(_, matcher)| matcher.score(desc))
+                    .sum();
+                (exact, false, score)
+            }
+        }
+    }
+
+    /// Consumes the query, returning a matcher that highlights every name term that was not
+    /// negated.
+    pub fn into_highlighter(self) -> Box<Highlighter> {
+        Box::new(AnyOf(
+            self.positive
+                .into_iter()
+                .map(|(_, matcher)| matcher)
+                .collect(),
+        ))
+    }
+}
+
+impl Node {
+    fn matches(
+        &self,
+        index: usize,
+        packages: &Packages,
+        installed: &Installed,
+        descriptions: bool,
+    ) -> bool {
+        match self {
+            Node::And(nodes) => nodes
+                .iter()
+                .all(|node| node.matches(index, packages, installed, descriptions)),
+            Node::Or(nodes) => nodes
+                .iter()
+                .any(|node| node.matches(index, packages, installed, descriptions)),
+            Node::Not(node) => !node.matches(index, packages, installed, descriptions),
+            Node::Term(term) => term.matches(index, packages, installed, descriptions),
+        }
+    }
+}
+
+impl Term {
+    fn matches(
+        &self,
+        index: usize,
+        packages: &Packages,
+        installed: &Installed,
+        descriptions: bool,
+    ) -> bool {
+        let description = || packages.description(index);
+
+        match self {
+            Term::Bare(matcher) => {
+                matcher.matches(&packages[index])
+                    || descriptions && description().is_some_and(|desc| matcher.matches(desc))
+            }
+            Term::Name(matcher) => matcher.matches(&packages[index]),
+            Term::Desc(matcher) => description().is_some_and(|desc| matcher.matches(desc)),
+            Term::Repo(repo) => packages.repo(index) == &**repo,
+            Term::Installed(expected) => installed.contains(&index) == *expected,
+            Term::Upgradable(expected) => installed.is_outdated(&index) == *expected,
+            Term::Maintainer(matcher) => packages
+                .maintainer(index)
+                .is_some_and(|maintainer| matcher.matches(maintainer)),
+            Term::Votes(comparison, expected) => packages
+                .votes(index)
+                .is_some_and(|votes| comparison.compare(votes, *expected)),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+    fuzzy: bool,
+    negated: bool,
+    positive: Vec<(Box<str>, Arc<Highlighter>)>,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Node, Message> {
+        let mut nodes = vec![self.and()?];
+        while let Some(Token::Or) = self.tokens.peek() {
+            self.tokens.next();
+            nodes.push(self.and()?);
+        }
+
+        Ok(if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::Or(nodes)
+        })
+    }
+
+    fn and(&mut self) -> Result<Node, Message> {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.tokens.peek() {
+            if matches!(token, Token::Or | Token::Close) {
+                break;
+            }
+            nodes.push(self.unary()?);
+        }
+
+        match nodes.len() {
+            0 => Err(Message::InvalidSyntax),
+            1 => Ok(nodes.pop().unwrap()),
+            _ => Ok(Node::And(nodes)),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, Message> {
+        match self.tokens.next() {
+            Some(Token::Not) => {
+                self.negated = !self.negated;
+                let node = self.unary();
+                self.negated = !self.negated;
+                Ok(Node::Not(Box::new(node?)))
+            }
+            Some(Token::Open) => {
+                let node = self.or()?;
+                match self.tokens.next() {
+                    Some(Token::Close) => Ok(node),
+                    _ => Err(Message::InvalidSyntax),
+                }
+            }
+            Some(Token::Word(word)) => self.term(&word).map(Node::Term),
+            Some(Token::Quoted(word)) => self.bare(&word).map(Node::Term),
+            _ => Err(Message::InvalidSyntax),
+        }
+    }
+
+    fn term(&mut self, word: &str) -> Result<Term, Message> {
+        let field = word.split_once(':').filter(|(field, _)| {
+            !field.is_empty() && field.bytes().all(|b| b.is_ascii_lowercase())
+        });
+
+        let Some((field, value)) = field else {
+            return self.bare(word);
+        };
+
+        Ok(match field {
+            "name" => {
+                let matcher = self.matcher(value)?;
+                if !self.negated {
+                    self.positive.push((value.into(), matcher.clone()));
+                }
+                Term::Name(matcher)
+            }
+            "desc" | "description" => Term::Desc(self.matcher(value)?),
+            "repo" => Term::Repo(value.to_ascii_lowercase().into()),
+            "installed" => Term::Installed(parse_bool(value)?),
+            "upgradable" | "outdated" => Term::Upgradable(parse_bool(value)?),
+            "maint" | "maintainer" => Term::Maintainer(self.matcher(value)?),
+            "votes" => {
+                let (comparison, value) = Comparison::parse(value);
+                let votes = value.parse().map_err(|_| Message::InvalidValue)?;
+                Term::Votes(comparison, votes)
+            }
+            _ => return Err(Message::UnknownField),
+        })
+    }
+
+    /// Matches `word` against the name, and the description if descriptions are searched.
+    fn bare(&mut self, word: &str) -> Result<Term, Message> {
+        let matcher = self.matcher(word)?;
+        if !self.negated {
+            self.positive.push((word.into(), matcher.clone()));
+        }
+        Ok(Term::Bare(matcher))
+    }
+
+    fn matcher(&self, value: &str) -> Result<Arc<Highlighter>, Message> {
+        if self.fuzzy {
+            Ok(Arc::new(Fuzzy::new(value)))
+        } else {
+            Regex::new(value)
+                .map(|re| Arc::new(re) as Arc<Highlighter>)
+                .map_err(|_| Message::InvalidRegex)
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, Message> {
+    match value {
+        "yes" | "true" | "y" => Ok(true),
+        "no" | "false" | "n" => Ok(false),
+        _ => Err(Message::InvalidValue),
+    }
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    // Whether the word starts with a quote.
+    let mut literal = false;
+    let mut in_quotes = false;
+
+    let mut push = |word: &mut String, quoted: bool, literal: bool| {
+        if word.is_empty() && !quoted {
+            return;
+        }
+
+        let word = std::mem::take(word);
+        if literal {
+            tokens.push(Token::Quoted(word));
+            return;
+        }
+        if quoted {
+            tokens.push(Token::Word(word));
+            return;
+        }
+
+        match word.as_str() {
+            "(" => tokens.push(Token::Open),
+            ")" => tokens.push(Token::Close),
+            "|" | "OR" => tokens.push(Token::Or),
+            "!" | "NOT" => tokens.push(Token::Not),
+            _ => match word.strip_prefix('!') {
+                Some(rest) => {
+                    tokens.push(Token::Not);
+                    tokens.push(Token::Word(rest.to_owned()));
+                }
+                None => tokens.push(Token::Word(word)),
+            },
+        }
+    };
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                literal |= !quoted && word.is_empty();
+                quoted = true;
+                in_quotes = !in_quotes;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                push(&mut word, quoted, literal);
+                quoted = false;
+                literal = false;
+            }
+            c => word.push(c),
+        }
+    }
+    push(&mut word, quoted, literal);
+
+    tokens
+}
+
+/// Matches if any of its matchers match, highlighting the ranges of all of them.
+struct AnyOf(Vec<Arc<Highlighter>>);
+
+impl<'a> Matcher<&'a str> for AnyOf {
+    fn matches(&self, value: &'a str) -> bool {
+        self.0.iter().any(|matcher| matcher.matches(value))
+    }
+
+    fn ranges(&self, value: &'a str) -> Vec<Range<usize>> {
+        let mut ranges = self
+            .0
+            .iter()
+            .flat_map(|matcher| matcher.ranges(value))
+            .collect::<Vec<_>>();
+        ranges.sort_unstable_by_key(|range| range.start);
+
+        // Merge overlapping ranges so that no part of `value` is rendered twice.
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_fields() {
+        let query = Query::parse("repo:extra installed:yes name:vim", false, false).unwrap();
+        assert_eq!(query.positive.len(), 1);
+        assert_eq!(&*query.positive[0].0, "vim");
+    }
+
+    #[test]
+    fn unknown_fields() {
+        for query in ["perl:", "std::", "foo:bar", "repo:extra owns:/usr/bin/rg"] {
+            assert!(
+                matches!(
+                    Query::parse(query, false, false),
+                    Err(Message::UnknownField)
+                ),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn quoted_terms_are_never_fields() {
+        for (query, term) in [("\"std::\"", "std::"), ("\"foo:bar baz\"", "foo:bar baz")] {
+            let query = Query::parse(query, false, false).unwrap();
+            assert_eq!(query.positive.len(), 1);
+            assert_eq!(&*query.positive[0].0, term);
+        }
+
+        // Quotes inside a word only keep the spaces of the value.
+        let query = Query::parse("name:\"a b\"", false, false).unwrap();
+        assert_eq!(&*query.positive[0].0, "a b");
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(Query::parse("installed:maybe", false, false).is_err());
+        assert!(Query::parse("votes:>many", false, false).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    diff::{unified, DiffLine},
+    packages::{Installed, Packages},
+    pkgbuild::Fetched,
+};
+
+/// The unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+/// The name of a reviewed PKGBUILD waiting for its package to be installed, next to the
+/// PKGBUILD it replaces.
+const PENDING: &str = "PKGBUILD.pending";
+
+/// The PKGBUILD of an AUR package about to be built, compared to the one last reviewed.
+pub struct Entry {
+    /// The index of the package.
+    pub index: usize,
+    pub fetched: Fetched,
+    /// The changes since the PKGBUILD was last reviewed, `None` if it never was.
+    pub diff: Option<Vec<DiffLine>>,
+}
+
+impl Entry {
+    /// Compares the fetched PKGBUILD to the reviewed one, reading it from the store.
+    pub fn new(index: usize, fetched: Fetched) -> Self {
+        let diff = match &fetched {
+            Fetched::Loaded(pkgbuild) => read_reviewed(&pkgbuild.base)
+                .map(|reviewed| unified(&reviewed, &pkgbuild.text, CONTEXT)),
+            Fetched::Loading | Fetched::Unavailable(_) => None,
+        };
+
+        Self {
+            index,
+            fetched,
+            diff,
+        }
+    }
+}
+
+/// The PKGBUILDs of the AUR packages in a transaction, shown before handing off to the helper.
+pub struct Review {
+    pub entries: Vec<Entry>,
+    /// Whether the AUR packages the transaction pulls in are still being looked up.
+    pub resolving: bool,
+    /// The first visible line.
+    pub scroll: usize,
+    /// The helper to run once the PKGBUILDs are reviewed, along with what to print before it.
+    pub handoff: (Command, Vec<u8>),
+}
+
+impl Review {
+    pub fn new(targets: &[usize], handoff: (Command, Vec<u8>)) -> Self {
+        let mut review = Self {
+            entries: Vec::with_capacity(targets.len()),
+            resolving: false,
+            scroll: 0,
+            handoff,
+        };
+        for &index in targets {
+            review.add(index);
+        }
+        review
+    }
+
+    /// Adds the package at `index` to be fetched, unless it is already part of the review.
+    pub fn add(&mut self, index: usize) -> bool {
+        if self.entries.iter().any(|entry| entry.index == index) {
+            return false;
+        }
+
+        self.entries.push(Entry {
+            index,
+            fetched: Fetched::Loading,
+            diff: None,
+        });
+        true
+    }
+
+    /// Replaces the entry of the same package, unless the review was replaced in the meantime.
+    pub fn set(&mut self, entry: Entry) {
+        if let Some(old) = self.entries.iter_mut().find(|old| old.index == entry.index) {
+            *old = entry;
+        }
+    }
+
+    /// Whether every PKGBUILD has been fetched or failed to be, so that none can go unseen.
+    pub fn is_done(&self) -> bool {
+        !self.resolving
+            && self
+                .entries
+                .iter()
+                .all(|entry| !matches!(entry.fetched, Fetched::Loading))
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    /// Scrolls down by `rows`, stopping once the last of `lines` is visible in a pane `height`
+    /// rows high.
+    pub fn scroll_down(&mut self, rows: usize, height: usize, lines: usize) {
+        let last = lines.saturating_sub(height);
+        self.scroll = (self.scroll + rows).min(last.max(self.scroll));
+    }
+
+    /// Returns every fetched PKGBUILD, to be stored as reviewed once the helper built them.
+    pub fn reviewed(&self, packages: &Packages) -> Vec<Reviewed> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match &entry.fetched {
+                Fetched::Loaded(pkgbuild) => Some(Reviewed {
+                    name: packages[entry.index].to_owned(),
+                    version: packages.version(entry.index)?.to_owned(),
+                    base: pkgbuild.base.to_string(),
+                    text: pkgbuild.text.clone(),
+                }),
+                Fetched::Loading | Fetched::Unavailable(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// A PKGBUILD that was reviewed before building the package.
+pub struct Reviewed {
+    name: String,
+    /// The version of the package in the AUR when the PKGBUILD was reviewed.
+    version: String,
+    base: String,
+    text: String,
+}
+
+/// Stores the PKGBUILDs as reviewed, so that only the changes to them are shown next time.
+pub fn store(reviewed: &[Reviewed]) -> io::Result<()> {
+    match store_dir() {
+        Some(dir) => write(&dir, reviewed, false),
+        None => Ok(()),
+    }
+}
+
+/// Stores the PKGBUILDs as pending, for when parui is no longer running once the helper exits.
+///
+/// They are stored as reviewed by [`commit_pending`] on the next start if their package got
+/// installed at the version they were reviewed at.
+pub fn store_pending(reviewed: &[Reviewed]) -> io::Result<()> {
+    match store_dir() {
+        Some(dir) => write(&dir, reviewed, true),
+        None => Ok(()),
+    }
+}
+
+/// Stores the pending PKGBUILDs whose package is installed at the version they were reviewed at
+/// as reviewed, and discards the others as the helper failed to build them.
+pub fn commit_pending(installed: &Installed) -> io::Result<()> {
+    match store_dir() {
+        Some(dir) => commit_pending_in(&dir, installed),
+        None => Ok(()),
+    }
+}
+
+/// Writes the PKGBUILDs to the directories of their bases in `dir`. Pending ones start with a
+/// line naming the package and the version it was reviewed at.
+fn write(dir: &Path, reviewed: &[Reviewed], pending: bool) -> io::Result<()> {
+    for reviewed in reviewed {
+        if !is_valid_base(&reviewed.base) {
+            continue;
+        }
+
+        let base_dir = dir.join(&reviewed.base);
+        fs::create_dir_all(&base_dir)?;
+        if pending {
+            let contents = format!("{} {}\n{}", reviewed.name, reviewed.version, reviewed.text);
+            fs::write(base_dir.join(PENDING), contents)?;
+        } else {
+            fs::write(base_dir.join("PKGBUILD"), &reviewed.text)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn commit_pending_in(dir: &Path, installed: &Installed) -> io::Result<()> {
+    let bases = match fs::read_dir(dir) {
+        Ok(bases) => bases,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for base in bases {
+        let base_dir = base?.path();
+        let pending = base_dir.join(PENDING);
+        let Ok(contents) = fs::read_to_string(&pending) else {
+            continue;
+        };
+
+        let built = contents.split_once('\n').filter(|(header, _)| {
+            header.split_once(' ').is_some_and(|(name, version)| {
+                installed
+                    .find(name)
+                    .is_some_and(|package| *package.version == *version)
+            })
+        });
+        if let Some((_, text)) = built {
+            fs::write(base_dir.join("PKGBUILD"), text)?;
+        }
+        fs::remove_file(pending)?;
+    }
+
+    Ok(())
+}
+
+fn read_reviewed(base: &str) -> Option<String> {
+    if !is_valid_base(base) {
+        return None;
+    }
+    fs::read_to_string(store_dir()?.join(base).join("PKGBUILD")).ok()
+}
+
+fn is_valid_base(base: &str) -> bool {
+    // Package bases never contain slashes, but they come from the network.
+    !base.is_empty() && !base.starts_with('.') && !base.contains('/')
+}
+
+/// Returns `$XDG_DATA_HOME/parui/pkgbuilds`, falling back to `~/.local/share`. The PKGBUILDs
+/// are stored in a directory for each package base.
+fn store_dir() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            Some(
+                Path::new(&env::var_os("HOME")?)
+                    .join(".local")
+                    .join("share"),
+            )
+        })?;
+
+    Some(data_home.join("parui").join("pkgbuilds"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use nohash_hasher::IntSet;
+
+    use super::*;
+    use crate::db::LocalPackage;
+
+    fn reviewed(name: &str, version: &str, text: &str) -> Reviewed {
+        Reviewed {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            base: name.to_owned(),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn pending_pkgbuilds() {
+        let dir = env::temp_dir().join(format!("parui-review-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        write(&dir, &[reviewed("built", "1.0-1", "old")], false).unwrap();
+        write(
+            &dir,
+            &[
+                reviewed("built", "1.1-1", "new"),
+                reviewed("failed", "2.0-1", "broken"),
+                reviewed("../escape", "1.0-1", "evil"),
+            ],
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("built").join("PKGBUILD")).unwrap(),
+            "old"
+        );
+
+        let installed = Installed::new(
+            vec![
+                LocalPackage {
+                    name: "built".into(),
+                    version: "1.1-1".into(),
+                    ..LocalPackage::default()
+                },
+                LocalPackage {
+                    name: "failed".into(),
+                    version: "1.9-1".into(),
+                    ..LocalPackage::default()
+                },
+            ],
+            [(0, 0), (1, 1)].into_iter().collect(),
+            IntSet::default(),
+        );
+        commit_pending_in(&dir, &installed).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("built").join("PKGBUILD")).unwrap(),
+            "new"
+        );
+        assert!(!dir.join("failed").join("PKGBUILD").exists());
+        assert!(!dir.join("built").join(PENDING).exists());
+        assert!(!dir.join("failed").join(PENDING).exists());
+        assert!(!dir.join("escape").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
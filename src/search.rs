@@ -0,0 +1,411 @@
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::{
+    files::owners,
+    matcher::Highlighter,
+    message::Message,
+    packages::{Installed, Packages},
+    query::Query,
+    shown::Shown,
+};
+
+/// How often a running search checks whether it has been superseded.
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+/// Prefixes of queries looking for the packages owning a path instead of matching packages.
+const FILE_PREFIXES: [&str; 2] = ["owns:", "file:"];
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    pub descriptions: bool,
+    pub fuzzy: bool,
+}
+
+impl Search {
+    pub fn new(query: &str, descriptions: bool, fuzzy: bool) -> Self {
+        Self {
+            query: query.to_owned(),
+            descriptions,
+            fuzzy,
+        }
+    }
+
+    /// Returns the path a query like `owns:/usr/bin/rg` or `file:rg` looks for.
+    fn file_lookup(&self) -> Option<&str> {
+        let query = self.query.trim();
+        FILE_PREFIXES
+            .iter()
+            .find_map(|prefix| query.strip_prefix(prefix))
+            .map(str::trim)
+    }
+
+    /// Whether every result of `self` is also a result of `previous`, in which case only the
+    /// results of `previous` need to be searched.
+    fn narrows(&self, previous: &Search) -> bool {
+        let query = self.query.trim();
+        let previous_query = previous.query.trim();
+
+        self.descriptions == previous.descriptions
+            && self.fuzzy == previous.fuzzy
+            && !previous_query.is_empty()
+            && query.starts_with(previous_query)
+            && Query::is_plain(query, self.fuzzy)
+    }
+}
+
+pub enum SearchError {
+    Cancelled,
+    Invalid(Message),
+}
+
+/// Runs searches, making sure that only the latest one gets to write its results.
+#[derive(Default)]
+pub struct Searcher {
+    generation: AtomicUsize,
+    /// The latest search that was started.
+    latest: Mutex<Search>,
+    /// The generation the latest search was started as.
+    started: AtomicUsize,
+    /// The search that produced the results currently shown.
+    last: Mutex<Option<Search>>,
+    /// The results before the current ones, kept around to be reused.
+    spare: Mutex<Vec<usize>>,
+}
+
+impl Searcher {
+    /// Registers `search` as the latest search, cancelling any search that is still running.
+    pub fn start(&self, search: Search) -> usize {
+        let mut latest = self.latest.lock();
+        *latest = search;
+        let id = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.started.store(id, Ordering::SeqCst);
+        id
+    }
+
+    pub fn latest(&self) -> Search {
+        self.latest.lock().clone()
+    }
+
+    /// Returns the id `search` was started as if it is the latest search and nothing superseded
+    /// it, in which case it is either still running or done.
+    pub fn pending(&self, search: &Search) -> Option<usize> {
+        let latest = self.latest.lock();
+        let id = self.generation.load(Ordering::SeqCst);
+        (*latest == *search && self.started.load(Ordering::SeqCst) == id).then_some(id)
+    }
+
+    /// Whether the results currently shown are those of `search`.
+    pub fn shows(&self, search: &Search) -> bool {
+        self.last.lock().as_ref() == Some(search)
+    }
+
+    /// Runs `search`, which must have been started as `id`, and writes its results to `shown`.
+    ///
+    /// Queries starting with `owns:` or `file:` list the packages owning the rest of the query,
+    /// which reads every files database.
+    ///
+    /// If `search` only narrows down the search that produced the current results, only those are
+    /// searched again. Returns the matcher used so that the results can be highlighted.
+    pub fn run(
+        &self,
+        id: usize,
+        search: &Search,
+        packages: &Packages,
+        installed: &Installed,
+        shown: &RwLock<Shown>,
+    ) -> Result<Option<Box<Highlighter>>, SearchError> {
+        let cancelled = || self.generation.load(Ordering::SeqCst) != id;
+
+        let query = search.query.trim();
+        if query.is_empty() || query == ".*" || query == "." {
+            self.write(shown, cancelled, Shown::replace_all, Some(search))?;
+            return Ok(None);
+        }
+
+        if let Some(path) = search.file_lookup() {
+            if path.is_empty() {
+                self.write(shown, cancelled, |shown| shown.replace(Vec::new()), None)?;
+                return Err(SearchError::Invalid(Message::MissingPath));
+            }
+
+            let out = owners(path, packages, installed, cancelled).ok_or(SearchError::Cancelled)?;
+            self.write(shown, cancelled, |shown| shown.replace(out), Some(search))?;
+            return Ok(None);
+        }
+
+        let query = match Query::parse(query, search.descriptions, search.fuzzy) {
+            Ok(query) => query,
+            Err(message) => {
+                self.write(shown, cancelled, |shown| shown.replace(Vec::new()), None)?;
+                return Err(SearchError::Invalid(message));
+            }
+        };
+
+        let mut out = std::mem::take(&mut *self.spare.lock());
+        out.clear();
+
+        {
+            let handle = shown.read();
+
+            let narrow = self
+                .last
+                .lock()
+                .as_ref()
+                .is_some_and(|last| search.narrows(last));
+
+            let candidates: Box<dyn Iterator<Item = usize>> = match handle.get_vec() {
+                Some(results) if narrow => Box::new(results.iter().copied()),
+                _ => Box::new(0..packages.len()),
+            };
+
+            for (n, i) in candidates.enumerate() {
+                if n % CANCEL_CHECK_INTERVAL == 0 && cancelled() {
+                    return Err(SearchError::Cancelled);
+                }
+
+                if query.matches(i, packages, installed) {
+                    out.push(i);
+                }
+            }
+        }
+
+        if search.fuzzy {
+            let mut ranked = out
+                .drain(..)
+                .map(|i| ((query.rank(i, packages), Reverse(packages[i].len())), i))
+                .collect::<Vec<_>>();
+            ranked.sort_unstable_by(|(a, a_idx), (b, b_idx)| b.cmp(a).then(a_idx.cmp(b_idx)));
+            out.extend(ranked.into_iter().map(|(_, i)| i));
+        }
+
+        self.write(shown, cancelled, |shown| shown.replace(out), Some(search))?;
+        Ok(Some(query.into_highlighter()))
+    }
+
+    /// Shows `results`, which did not come from a search, cancelling any search that is still
+    /// running.
+    pub fn show(&self, results: Vec<usize>, shown: &RwLock<Shown>) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.spare.lock() = shown.write().replace(results);
+        *self.last.lock() = None;
+    }
+
+    /// Writes the results of `search` to `shown` unless the search was cancelled.
+    fn write(
+        &self,
+        shown: &RwLock<Shown>,
+        cancelled: impl Fn() -> bool,
+        replace: impl FnOnce(&mut Shown) -> Vec<usize>,
+        search: Option<&Search>,
+    ) -> Result<(), SearchError> {
+        let mut handle = shown.write();
+        if cancelled() {
+            return Err(SearchError::Cancelled);
+        }
+
+        *self.spare.lock() = replace(&mut handle);
+        *self.last.lock() = search.cloned();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_strings::FixedCompactStrings;
+
+    use super::*;
+    use crate::{aur::AurPackage, shown::Shown};
+
+    /// AUR packages with the given names and descriptions.
+    fn packages(packages: &[(&str, &str)]) -> Packages {
+        Packages::new(
+            packages
+                .iter()
+                .map(|&(name, _)| name)
+                .collect::<FixedCompactStrings>(),
+            Vec::new(),
+            packages
+                .iter()
+                .map(|&(name, description)| AurPackage {
+                    name: name.into(),
+                    version: "1.0-1".into(),
+                    description: Some(description.into()),
+                    maintainer: None,
+                    num_votes: 0,
+                })
+                .collect(),
+        )
+    }
+
+    /// Runs `search` as the latest search, returning the names of the results in order.
+    fn run(
+        searcher: &Searcher,
+        search: Search,
+        packages: &Packages,
+        shown: &RwLock<Shown>,
+    ) -> Vec<String> {
+        let id = searcher.start(search.clone());
+        assert!(searcher
+            .run(id, &search, packages, &Installed::default(), shown)
+            .is_ok());
+        names(packages, shown)
+    }
+
+    fn names(packages: &Packages, shown: &RwLock<Shown>) -> Vec<String> {
+        match shown.read().get_vec() {
+            Some(results) => results.iter().map(|&i| packages[i].to_owned()).collect(),
+            None => packages.iter().map(ToOwned::to_owned).collect(),
+        }
+    }
+
+    #[test]
+    fn fuzzy_ranking() {
+        let packages = packages(&[
+            ("ripgrep", "A line-oriented search tool"),
+            ("other", "Recursive grep"),
+            ("foo-rg", "Foo"),
+            ("rg", "Alias"),
+            ("unrelated", "Nothing"),
+        ]);
+        let shown = RwLock::new(Shown::All);
+        let searcher = Searcher::default();
+
+        // Exact names come first, then names by score and finally description matches.
+        assert_eq!(
+            run(&searcher, Search::new("rg", true, true), &packages, &shown),
+            ["rg", "foo-rg", "ripgrep", "other"]
+        );
+        assert_eq!(
+            run(&searcher, Search::new("RG", true, true), &packages, &shown),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn exact_name_first() {
+        let packages = packages(&[("python-ripgrep", ""), ("ripgrep-all", ""), ("ripgrep", "")]);
+        let shown = RwLock::new(Shown::All);
+
+        assert_eq!(
+            run(
+                &Searcher::default(),
+                Search::new("ripgrep", false, true),
+                &packages,
+                &shown
+            ),
+            ["ripgrep", "ripgrep-all", "python-ripgrep"]
+        );
+    }
+
+    #[test]
+    fn regex_order() {
+        let packages = packages(&[("b-rg", ""), ("a", ""), ("rg", "")]);
+        let shown = RwLock::new(Shown::All);
+
+        // Without fuzzy matching, results keep the order of the packages.
+        assert_eq!(
+            run(
+                &Searcher::default(),
+                Search::new("rg", false, false),
+                &packages,
+                &shown
+            ),
+            ["b-rg", "rg"]
+        );
+    }
+
+    #[test]
+    fn narrowing_searches() {
+        let previous = Search::new("rip", false, false);
+        assert!(Search::new("ripg", false, false).narrows(&previous));
+        assert!(Search::new("rip grep", false, false).narrows(&previous));
+
+        // Toggles, edits and anything but plain terms may find other packages.
+        assert!(!Search::new("ripg", true, false).narrows(&previous));
+        assert!(!Search::new("ripg", false, true).narrows(&previous));
+        assert!(!Search::new("ri", false, false).narrows(&previous));
+        assert!(!Search::new("rip|foo", false, false).narrows(&previous));
+        assert!(!Search::new("rip.", false, false).narrows(&previous));
+        assert!(!Search::new("rip OR foo", false, false).narrows(&previous));
+        assert!(!Search::new("rip", false, false).narrows(&Search::default()));
+
+        // Fuzzy terms are never regexes.
+        let previous = Search::new("rip", false, true);
+        assert!(Search::new("rip.", false, true).narrows(&previous));
+    }
+
+    #[test]
+    fn narrowed_searches_reuse_results() {
+        let packages = packages(&[("ripgrep", ""), ("ripgrep-all", ""), ("grip", "")]);
+        let shown = RwLock::new(Shown::All);
+        let searcher = Searcher::default();
+
+        assert_eq!(
+            run(
+                &searcher,
+                Search::new("rip", false, false),
+                &packages,
+                &shown
+            ),
+            ["ripgrep", "ripgrep-all", "grip"]
+        );
+
+        // Only the current results are searched again, so packages missing from them stay out.
+        shown.write().replace(vec![0, 2]);
+        assert_eq!(
+            run(
+                &searcher,
+                Search::new("ripg", false, false),
+                &packages,
+                &shown
+            ),
+            ["ripgrep"]
+        );
+
+        // Searches that do not narrow search every package.
+        assert_eq!(
+            run(
+                &searcher,
+                Search::new("ripgrep-", false, false),
+                &packages,
+                &shown
+            ),
+            ["ripgrep-all"]
+        );
+        assert_eq!(
+            run(&searcher, Search::new("", false, false), &packages, &shown),
+            ["ripgrep", "ripgrep-all", "grip"]
+        );
+    }
+
+    #[test]
+    fn superseded_searches_are_cancelled() {
+        let packages = packages(&[("ripgrep", ""), ("grip", "")]);
+        let shown = RwLock::new(Shown::All);
+        let searcher = Searcher::default();
+
+        let search = Search::new("rip", false, false);
+        let stale = searcher.start(search.clone());
+        let latest = searcher.start(Search::new("grip", false, false));
+        assert_ne!(stale, latest);
+
+        assert!(matches!(
+            searcher.run(stale, &search, &packages, &Installed::default(), &shown),
+            Err(SearchError::Cancelled)
+        ));
+        assert!(*shown.read() == Shown::All);
+
+        // Showing results that did not come from a search cancels searches as well.
+        searcher.show(vec![1], &shown);
+        assert!(matches!(
+            searcher.run(latest, &search, &packages, &Installed::default(), &shown),
+            Err(SearchError::Cancelled)
+        ));
+        assert_eq!(names(&packages, &shown), ["grip"]);
+    }
+}
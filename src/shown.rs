@@ -34,21 +34,24 @@ impl Shown {
         }
     }
 
-    pub fn clear(&mut self) {
+    /// Shows `results`, returning the buffer of the previous results so that it can be reused.
+    pub fn replace(&mut self, results: Vec<usize>) -> Vec<usize> {
         use Shown::*;
 
-        match self {
-            All => *self = Shown::Few(Vec::new()),
-            Few(v) => v.clear(),
+        match std::mem::replace(self, Few(results)) {
+            All => Vec::new(),
+            Few(v) => v,
         }
     }
 
-    pub fn extend(&mut self, iter: impl Iterator<Item = usize>) {
+    /// Shows every package, returning the buffer of the previous results so that it can be
+    /// reused.
+    pub fn replace_all(&mut self) -> Vec<usize> {
         use Shown::*;
 
-        match self {
-            All => (),
-            Few(v) => v.extend(iter),
+        match std::mem::replace(self, All) {
+            All => Vec::new(),
+            Few(v) => v,
         }
     }
 }
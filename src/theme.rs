@@ -0,0 +1,285 @@
+use tui::style::{Color, Modifier, Style};
+
+use crate::{mode::Mode, style};
+
+/// The styles of every element parui draws.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// The index in front of each result.
+    pub index: Style,
+    pub installed: Style,
+    /// An installed package under the cursor.
+    pub installed_current: Style,
+    /// An installed package with a newer version available.
+    pub outdated: Style,
+    /// An outdated package under the cursor.
+    pub outdated_current: Style,
+    pub uninstalled: Style,
+    /// An uninstalled package under the cursor.
+    pub uninstalled_current: Style,
+    /// The parts of a result that matched the query, patched onto the style of the result.
+    pub matched: Style,
+    /// The marker after selected packages.
+    pub selected_marker: Style,
+    /// The marker after packages installed as a dependency.
+    pub dependency_marker: Style,
+    pub repo: Style,
+    pub version: Style,
+    /// The installed version of a package that differs from the available one.
+    pub installed_version: Style,
+    pub description: Style,
+    /// The borders and title of the focused pane.
+    pub active: Style,
+    /// The borders and title of the unfocused pane.
+    pub inactive: Style,
+    /// The following are patched onto `active` in their mode.
+    pub insert: Style,
+    pub select: Style,
+    pub confirm: Style,
+    pub tree: Style,
+    pub files: Style,
+    pub pkgbuild: Style,
+    pub review: Style,
+    /// The field names in the info pane.
+    pub info_key: Style,
+    pub install_hint: Style,
+    pub remove_hint: Style,
+    /// Shown while the info of a package is being fetched.
+    pub loading: Style,
+    pub popup: Style,
+    pub popup_title: Style,
+    /// Comments in PKGBUILDs.
+    pub pkgbuild_comment: Style,
+    /// Quoted strings in PKGBUILDs.
+    pub pkgbuild_string: Style,
+    /// Variables assigned or expanded in PKGBUILDs.
+    pub pkgbuild_variable: Style,
+    /// Shell keywords in PKGBUILDs.
+    pub pkgbuild_keyword: Style,
+    /// Lines added to a PKGBUILD since it was last reviewed.
+    pub diff_added: Style,
+    /// Lines removed from a PKGBUILD since it was last reviewed.
+    pub diff_removed: Style,
+    /// The headers of the hunks of a diff.
+    pub diff_hunk: Style,
+}
+
+impl Theme {
+    pub const DEFAULT: Self = Self {
+        index: style!(Color::Gray),
+        installed: style! {
+            fg: Color::Green,
+            mod: Modifier::BOLD,
+        },
+        installed_current: style! {
+            fg: Color::Yellow,
+            bg: Color::Red,
+            mod: Modifier::BOLD,
+        },
+        outdated: style! {
+            fg: Color::Yellow,
+            mod: Modifier::BOLD,
+        },
+        outdated_current: style! {
+            fg: Color::Black,
+            bg: Color::Yellow,
+            mod: Modifier::BOLD,
+        },
+        uninstalled: style! {
+            fg: Color::LightBlue,
+            mod: Modifier::BOLD,
+        },
+        uninstalled_current: style! {
+            fg: Color::Blue,
+            bg: Color::Red,
+            mod: Modifier::BOLD,
+        },
+        matched: style! {
+            fg: Color::Magenta,
+            mod: Modifier::UNDERLINED,
+        },
+        selected_marker: style! {
+            fg: Color::Yellow,
+            mod: Modifier::BOLD,
+        },
+        dependency_marker: style!(Color::DarkGray),
+        repo: style!(Color::Magenta),
+        version: style!(Color::Green),
+        installed_version: style!(Color::Yellow),
+        description: style!(Color::Gray),
+        active: style! {
+            fg: Color::White,
+            mod: Modifier::BOLD,
+        },
+        inactive: style!(Color::Gray),
+        insert: style!(),
+        select: style!(),
+        confirm: style!(),
+        tree: style!(),
+        files: style!(),
+        pkgbuild: style!(),
+        review: style!(),
+        info_key: style! { mod: Modifier::BOLD },
+        install_hint: style! {
+            fg: Color::Green,
+            mod: Modifier::BOLD,
+        },
+        remove_hint: style! {
+            fg: Color::Red,
+            mod: Modifier::BOLD,
+        },
+        loading: style!(Color::Gray),
+        popup: style!(),
+        popup_title: style! { mod: Modifier::BOLD },
+        pkgbuild_comment: style!(Color::DarkGray),
+        pkgbuild_string: style!(Color::Green),
+        pkgbuild_variable: style!(Color::Cyan),
+        pkgbuild_keyword: style! {
+            fg: Color::Yellow,
+            mod: Modifier::BOLD,
+        },
+        diff_added: style!(Color::Green),
+        diff_removed: style!(Color::Red),
+        diff_hunk: style!(Color::Cyan),
+    };
+
+    pub const HIGH_CONTRAST: Self = Self {
+        index: style!(Color::White),
+        installed: style! {
+            fg: Color::LightGreen,
+            mod: Modifier::BOLD,
+        },
+        installed_current: style! {
+            fg: Color::Black,
+            bg: Color::LightGreen,
+            mod: Modifier::BOLD,
+        },
+        outdated: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        outdated_current: style! {
+            fg: Color::Black,
+            bg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        uninstalled: style! {
+            fg: Color::LightCyan,
+            mod: Modifier::BOLD,
+        },
+        uninstalled_current: style! {
+            fg: Color::Black,
+            bg: Color::LightCyan,
+            mod: Modifier::BOLD,
+        },
+        matched: style! {
+            fg: Color::LightMagenta,
+            mod: Modifier::BOLD.union(Modifier::UNDERLINED),
+        },
+        selected_marker: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        dependency_marker: style!(Color::Gray),
+        repo: style!(Color::LightMagenta),
+        version: style!(Color::LightGreen),
+        installed_version: style!(Color::LightYellow),
+        description: style!(Color::White),
+        active: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        inactive: style!(Color::White),
+        insert: style!(),
+        select: style!(),
+        confirm: style!(),
+        tree: style!(),
+        files: style!(),
+        pkgbuild: style!(),
+        review: style!(),
+        info_key: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        install_hint: style! {
+            fg: Color::LightGreen,
+            mod: Modifier::BOLD,
+        },
+        remove_hint: style! {
+            fg: Color::LightRed,
+            mod: Modifier::BOLD,
+        },
+        loading: style!(Color::White),
+        popup: style!(Color::White),
+        popup_title: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        pkgbuild_comment: style!(Color::Gray),
+        pkgbuild_string: style!(Color::LightGreen),
+        pkgbuild_variable: style!(Color::LightCyan),
+        pkgbuild_keyword: style! {
+            fg: Color::LightYellow,
+            mod: Modifier::BOLD,
+        },
+        diff_added: style!(Color::LightGreen),
+        diff_removed: style!(Color::LightRed),
+        diff_hunk: style!(Color::LightCyan),
+    };
+
+    /// Only uses modifiers, for terminals without colours or users who set `NO_COLOR`.
+    pub const MONOCHROME: Self = Self {
+        index: style!(),
+        installed: style! { mod: Modifier::BOLD },
+        installed_current: style! { mod: Modifier::BOLD.union(Modifier::REVERSED) },
+        outdated: style! { mod: Modifier::BOLD.union(Modifier::ITALIC) },
+        outdated_current: style! {
+            mod: Modifier::BOLD.union(Modifier::ITALIC).union(Modifier::REVERSED),
+        },
+        uninstalled: style!(),
+        uninstalled_current: style! { mod: Modifier::REVERSED },
+        matched: style! { mod: Modifier::UNDERLINED },
+        selected_marker: style! { mod: Modifier::BOLD },
+        dependency_marker: style! { mod: Modifier::DIM },
+        repo: style!(),
+        version: style!(),
+        installed_version: style! { mod: Modifier::ITALIC },
+        description: style! { mod: Modifier::DIM },
+        active: style! { mod: Modifier::BOLD },
+        inactive: style! { mod: Modifier::DIM },
+        insert: style!(),
+        select: style!(),
+        confirm: style!(),
+        tree: style!(),
+        files: style!(),
+        pkgbuild: style!(),
+        review: style!(),
+        info_key: style! { mod: Modifier::BOLD },
+        install_hint: style! { mod: Modifier::BOLD },
+        remove_hint: style! { mod: Modifier::BOLD },
+        loading: style! { mod: Modifier::DIM },
+        popup: style!(),
+        popup_title: style! { mod: Modifier::BOLD },
+        pkgbuild_comment: style! { mod: Modifier::DIM },
+        pkgbuild_string: style! { mod: Modifier::ITALIC },
+        pkgbuild_variable: style!(),
+        pkgbuild_keyword: style! { mod: Modifier::BOLD },
+        diff_added: style! { mod: Modifier::BOLD },
+        diff_removed: style! { mod: Modifier::CROSSED_OUT },
+        diff_hunk: style! { mod: Modifier::DIM },
+    };
+
+    /// The borders and title of the focused pane in `mode`.
+    pub fn active(&self, mode: Mode) -> Style {
+        let style = match mode {
+            Mode::Insert => self.insert,
+            Mode::Select => self.select,
+            Mode::Confirm => self.confirm,
+            Mode::Tree => self.tree,
+            Mode::Files => self.files,
+            Mode::Pkgbuild => self.pkgbuild,
+            Mode::Review => self.review,
+        };
+        self.active.patch(style)
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+    process::Command,
+};
+
+use crate::{
+    aur::{self, AUR_URL},
+    db::dep_name,
+    packages::{Installed, Packages},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Install,
+    /// Installs packages from the upgrades view, which only differs from installing in wording.
+    Upgrade,
+    Remove,
+    /// Removes packages along with their configuration and the dependencies nothing else needs.
+    RemoveOrphans,
+}
+
+impl Operation {
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Operation::Install => " Install ",
+            Operation::Upgrade => " Upgrade ",
+            Operation::Remove => " Remove ",
+            Operation::RemoveOrphans => " Remove Orphans ",
+        }
+    }
+
+    const fn progressive(&self) -> &'static str {
+        match self {
+            Operation::Install => "Installing ",
+            Operation::Upgrade => "Upgrading ",
+            Operation::Remove | Operation::RemoveOrphans => "Removing ",
+        }
+    }
+
+    pub const fn removes(&self) -> bool {
+        matches!(self, Operation::Remove | Operation::RemoveOrphans)
+    }
+}
+
+/// A package pulled in by the targets of a transaction.
+pub struct Dependency {
+    /// The repository the dependency comes from, `aur` for AUR packages.
+    pub repo: Box<str>,
+    pub name: Box<str>,
+}
+
+/// Packages about to be handed to the helper, shown for confirmation first.
+pub struct Transaction {
+    pub operation: Operation,
+    /// The indices of the packages to install or remove.
+    pub targets: Vec<usize>,
+    /// The dependencies that are not installed yet, `None` until they have been resolved.
+    pub dependencies: Option<Vec<Dependency>>,
+}
+
+impl Transaction {
+    pub fn new(operation: Operation, targets: Vec<usize>) -> Self {
+        // Nothing gets pulled in by removing packages.
+        let dependencies = operation.removes().then(Vec::new);
+
+        Self {
+            operation,
+            targets,
+            dependencies,
+        }
+    }
+
+    /// Builds the invocation of `program` that carries out the transaction.
+    ///
+    /// `flags` are only passed when installing, as options like `--needed` make `-R` fail.
+    pub fn command(&self, program: &str, flags: &[String], packages: &Packages) -> Command {
+        let mut cmd = Command::new(program);
+        match self.operation {
+            Operation::Install | Operation::Upgrade => cmd.arg("-S").args(flags),
+            Operation::Remove => cmd.arg("-R"),
+            Operation::RemoveOrphans => cmd.arg("-Rns"),
+        };
+        cmd.args(self.targets.iter().map(|&i| &packages[i]));
+        cmd
+    }
+
+    /// Returns the AUR packages among the targets and, once they are resolved, the dependencies.
+    pub fn aur_packages(&self, packages: &Packages) -> Vec<usize> {
+        let dependencies = self
+            .dependencies
+            .iter()
+            .flatten()
+            .filter(|dependency| &*dependency.repo == "aur")
+            .filter_map(|dependency| packages.find(&dependency.name));
+
+        let mut aur = Vec::new();
+        for i in self.targets.iter().copied().chain(dependencies) {
+            if packages.get_aur(i).is_some() && !aur.contains(&i) {
+                aur.push(i);
+            }
+        }
+        aur
+    }
+
+    /// Describes the transaction as it is printed before running the helper.
+    pub fn message(&self, packages: &Packages) -> Vec<u8> {
+        let names = self
+            .targets
+            .iter()
+            .map(|&i| &packages[i])
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{}{names}.\n", self.operation.progressive()).into_bytes()
+    }
+}
+
+/// Finds every package that installing `targets` would pull in, that is dependencies which are
+/// neither installed nor provided by an installed package or another target.
+///
+/// Version constraints are ignored. Dependencies of AUR packages, including the packages needed
+/// to build them, are looked up through the AUR RPC, so this blocks on the network.
+pub fn resolve(packages: &Packages, installed: &Installed, targets: &[usize]) -> Vec<Dependency> {
+    let mut providers = HashMap::with_capacity(packages.sync.len());
+    for package in &packages.sync {
+        providers.insert(&*package.name, package);
+    }
+    for package in &packages.sync {
+        for provided in &package.provides {
+            providers.entry(dep_name(provided)).or_insert(package);
+        }
+    }
+
+    let mut satisfied = installed
+        .packages
+        .iter()
+        .flat_map(|package| {
+            iter::once(&*package.name).chain(package.provides.iter().map(|p| dep_name(p)))
+        })
+        .map(str::to_owned)
+        .collect::<HashSet<_>>();
+
+    let mut queue = Vec::new();
+    for &i in targets {
+        satisfied.insert(packages[i].to_owned());
+
+        match packages.get_sync(i) {
+            Some(package) => {
+                satisfied.extend(package.provides.iter().map(|p| dep_name(p).to_owned()));
+                queue.extend(package.depends.iter().map(|dep| dep.to_string()));
+            }
+            None => {
+                if let Some(info) = aur::info(AUR_URL, &packages[i]) {
+                    satisfied.extend(info.provides.iter().map(|p| dep_name(p).to_owned()));
+                    queue.extend(info.depends);
+                    queue.extend(info.make_depends);
+                }
+            }
+        }
+    }
+
+    let mut dependencies = Vec::new();
+    while let Some(dep) = queue.pop() {
+        let name = dep_name(&dep);
+        if !satisfied.insert(name.to_owned()) {
+            continue;
+        }
+
+        if let Some(package) = providers.get(name) {
+            satisfied.insert(package.name.to_string());
+            satisfied.extend(package.provides.iter().map(|p| dep_name(p).to_owned()));
+            queue.extend(package.depends.iter().map(|dep| dep.to_string()));
+            dependencies.push(Dependency {
+                repo: Box::from(&*package.repo),
+                name: package.name.clone(),
+            });
+        } else if let Some(info) = aur::info(AUR_URL, name) {
+            satisfied.extend(info.provides.iter().map(|p| dep_name(p).to_owned()));
+            queue.extend(info.depends);
+            queue.extend(info.make_depends);
+            dependencies.push(Dependency {
+                repo: Box::from("aur"),
+                name: info.name.into_boxed_str(),
+            });
+        }
+        // Anything else is left for the helper to complain about.
+    }
+
+    dependencies
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_strings::FixedCompactStrings;
+
+    use super::*;
+
+    fn args(operation: Operation) -> Vec<String> {
+        let packages = Packages::new(
+            ["vim", "git"].into_iter().collect::<FixedCompactStrings>(),
+            Vec::new(),
+            Vec::new(),
+        );
+        let flags = ["--needed".to_owned()];
+        Transaction::new(operation, vec![0, 1])
+            .command("paru", &flags, &packages)
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn flags_only_when_installing() {
+        assert_eq!(args(Operation::Install), ["-S", "--needed", "vim", "git"]);
+        assert_eq!(args(Operation::Upgrade), ["-S", "--needed", "vim", "git"]);
+        assert_eq!(args(Operation::Remove), ["-R", "vim", "git"]);
+        assert_eq!(args(Operation::RemoveOrphans), ["-Rns", "vim", "git"]);
+    }
+
+    #[test]
+    fn aur_packages_include_dependencies() {
+        let aur = |name: &str| crate::aur::AurPackage {
+            name: name.into(),
+            version: "1.0-1".into(),
+            description: None,
+            maintainer: None,
+            num_votes: 0,
+        };
+        let packages = Packages::new(
+            ["git", "paru", "libalpm-helper", "yay"]
+                .into_iter()
+                .collect::<FixedCompactStrings>(),
+            vec![crate::db::SyncPackage {
+                name: "git".into(),
+                ..Default::default()
+            }],
+            vec![aur("paru"), aur("libalpm-helper"), aur("yay")],
+        );
+        let dependency = |repo: &str, name: &str| Dependency {
+            repo: repo.into(),
+            name: name.into(),
+        };
+
+        let mut transaction = Transaction::new(Operation::Install, vec![0, 1]);
+        assert_eq!(transaction.aur_packages(&packages), [1]);
+
+        transaction.dependencies = Some(vec![
+            dependency("extra", "git"),
+            dependency("aur", "libalpm-helper"),
+            dependency("aur", "paru"),
+        ]);
+        assert_eq!(transaction.aur_packages(&packages), [1, 2]);
+    }
+}
@@ -0,0 +1,435 @@
+use crate::{
+    aur::AurInfo,
+    db::dep_name,
+    packages::{Installed, Packages},
+};
+
+/// Whether a tree follows the dependencies of packages or the installed packages depending on
+/// them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Dependencies,
+    Dependents,
+}
+
+/// How a node relates to its parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Root,
+    Depends,
+    OptDepends,
+    MakeDepends,
+    CheckDepends,
+    /// An installed package depending on its parent.
+    RequiredBy,
+    /// An installed package optionally depending on its parent.
+    OptionalFor,
+}
+
+impl Kind {
+    /// The label shown after the node, if any.
+    pub const fn label(&self) -> Option<&'static str> {
+        match self {
+            Kind::Root | Kind::Depends | Kind::RequiredBy => None,
+            Kind::OptDepends | Kind::OptionalFor => Some("optional"),
+            Kind::MakeDepends => Some("make"),
+            Kind::CheckDepends => Some("check"),
+        }
+    }
+}
+
+pub struct Node {
+    /// The dependency as it is written, with any version constraint.
+    pub label: Box<str>,
+    /// The name of the package or of what it provides, without the version constraint.
+    name: Box<str>,
+    pub kind: Kind,
+    /// The index of the package satisfying the dependency, if parui knows one.
+    pub index: Option<usize>,
+    pub installed: bool,
+    pub expanded: bool,
+    /// `None` until the children have been looked up.
+    pub children: Option<Vec<Node>>,
+}
+
+impl Node {
+    fn new(dep: &str, kind: Kind, packages: &Packages, installed: &Installed) -> Self {
+        // Optional dependencies are followed by a description.
+        let label = match kind {
+            Kind::OptDepends => dep.split(':').next().unwrap_or(dep).trim(),
+            _ => dep,
+        };
+        let name = dep_name(dep);
+
+        Self {
+            label: Box::from(label),
+            name: Box::from(name),
+            kind,
+            index: packages.find(name),
+            installed: installed.satisfies(name),
+            expanded: false,
+            children: None,
+        }
+    }
+
+    /// Whether the node may have children, which is only known for sure once they are looked up.
+    pub fn expandable(&self) -> bool {
+        let known = match self.kind {
+            Kind::RequiredBy | Kind::OptionalFor => self.installed,
+            _ => self.index.is_some(),
+        };
+        self.children
+            .as_ref()
+            .map_or(known, |children| !children.is_empty())
+    }
+}
+
+/// A row of the tree as it is drawn.
+pub struct Row<'a> {
+    pub node: &'a Node,
+    pub depth: usize,
+    /// The positions of the children leading from the root to the node.
+    path: Vec<usize>,
+}
+
+/// The dependencies or dependents of a package, looked up as nodes are expanded.
+pub struct Tree {
+    pub direction: Direction,
+    pub root: Node,
+    /// The position of the cursor among the visible rows.
+    pub cursor: usize,
+}
+
+impl Tree {
+    /// Creates the tree of the package at `index`, with the root expanded.
+    ///
+    /// The dependencies of AUR packages have to be fetched by the caller, see
+    /// [`Tree::set_children`].
+    pub fn new(
+        direction: Direction,
+        index: usize,
+        packages: &Packages,
+        installed: &Installed,
+    ) -> Self {
+        let mut root = Node::new(&packages[index], Kind::Root, packages, installed);
+        root.index = Some(index);
+        root.installed = installed.contains(&index);
+        root.expanded = true;
+        root.children = children(direction, &root, packages, installed);
+
+        Self {
+            direction,
+            root,
+            cursor: 0,
+        }
+    }
+
+    /// Returns the visible rows, depth first.
+    pub fn rows(&self) -> Vec<Row<'_>> {
+        fn visit<'a>(node: &'a Node, path: &mut Vec<usize>, rows: &mut Vec<Row<'a>>) {
+            rows.push(Row {
+                node,
+                depth: path.len(),
+                path: path.clone(),
+            });
+            if let (true, Some(children)) = (node.expanded, &node.children) {
+                for (i, child) in children.iter().enumerate() {
+                    path.push(i);
+                    visit(child, path, rows);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        visit(&self.root, &mut Vec::new(), &mut rows);
+        rows
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        path.iter().try_fold(&mut self.root, |node, &i| {
+            node.children.as_mut()?.get_mut(i)
+        })
+    }
+
+    pub fn current(&self) -> Option<&Node> {
+        self.rows().get(self.cursor).map(|row| row.node)
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.rows().len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Expands the node under the cursor.
+    ///
+    /// Returns the path to the node and its index if it is an AUR package, whose info has to be
+    /// fetched for its children, see [`Tree::set_children`].
+    pub fn expand(
+        &mut self,
+        packages: &Packages,
+        installed: &Installed,
+    ) -> Option<(Vec<usize>, usize)> {
+        let direction = self.direction;
+        let path = self.rows().get(self.cursor)?.path.clone();
+        let node = self.node_mut(&path)?;
+        if !node.expandable() {
+            return None;
+        }
+
+        node.expanded = true;
+        if node.children.is_some() {
+            return None;
+        }
+
+        node.children = children(direction, node, packages, installed);
+        match node.children {
+            Some(_) => None,
+            None => Some((path, node.index?)),
+        }
+    }
+
+    /// Collapses the node under the cursor, or moves the cursor to its parent if it is already
+    /// collapsed.
+    pub fn collapse(&mut self) {
+        let rows = self.rows();
+        let Some(row) = rows.get(self.cursor) else {
+            return;
+        };
+
+        if row.node.expanded {
+            let path = row.path.clone();
+            drop(rows);
+            if let Some(node) = self.node_mut(&path) {
+                node.expanded = false;
+            }
+        } else if let Some((_, parent)) = row.path.split_last() {
+            self.cursor = rows
+                .iter()
+                .position(|row| row.path == parent)
+                .unwrap_or_default();
+        }
+    }
+
+    /// Sets the children of the node at `path` from the info of its AUR package at `index`,
+    /// unless the node was replaced in the meantime.
+    pub fn set_children(
+        &mut self,
+        path: &[usize],
+        index: usize,
+        info: Option<&AurInfo>,
+        packages: &Packages,
+        installed: &Installed,
+    ) {
+        if let Some(node) = self
+            .node_mut(path)
+            .filter(|node| node.index == Some(index) && node.children.is_none())
+        {
+            node.children =
+                Some(info.map_or_else(Vec::new, |info| aur_children(info, packages, installed)));
+        }
+    }
+}
+
+/// Looks up the children of `node`, or returns `None` for the dependencies of AUR packages.
+fn children(
+    direction: Direction,
+    node: &Node,
+    packages: &Packages,
+    installed: &Installed,
+) -> Option<Vec<Node>> {
+    if direction == Direction::Dependents {
+        let Some(package) = installed.find(&node.name) else {
+            return Some(Vec::new());
+        };
+
+        return Some(nodes(
+            [
+                (Kind::RequiredBy, &*installed.required_by(package)),
+                (Kind::OptionalFor, &*installed.optional_for(package)),
+            ],
+            packages,
+            installed,
+        ));
+    }
+
+    let package = packages.get_sync(node.index?)?;
+
+    Some(nodes(
+        [
+            (Kind::Depends, &*package.depends),
+            (Kind::OptDepends, &*package.optdepends),
+            (Kind::MakeDepends, &*package.makedepends),
+            (Kind::CheckDepends, &*package.checkdepends),
+        ],
+        packages,
+        installed,
+    ))
+}
+
+fn aur_children(info: &AurInfo, packages: &Packages, installed: &Installed) -> Vec<Node> {
+    nodes(
+        [
+            (Kind::Depends, &*info.depends),
+            (Kind::OptDepends, &*info.opt_depends),
+            (Kind::MakeDepends, &*info.make_depends),
+            (Kind::CheckDepends, &*info.check_depends),
+        ],
+        packages,
+        installed,
+    )
+}
+
+fn nodes<S: AsRef<str>, const N: usize>(
+    deps: [(Kind, &[S]); N],
+    packages: &Packages,
+    installed: &Installed,
+) -> Vec<Node> {
+    deps.into_iter()
+        .flat_map(|(kind, deps)| {
+            deps.iter()
+                .map(move |dep| Node::new(dep.as_ref(), kind, packages, installed))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_strings::FixedCompactStrings;
+    use nohash_hasher::IntSet;
+
+    use super::*;
+    use crate::{
+        aur::AurPackage,
+        db::{LocalPackage, SyncPackage},
+    };
+
+    fn sync(name: &str, depends: &[&str], optdepends: &[&str], provides: &[&str]) -> SyncPackage {
+        SyncPackage {
+            name: name.into(),
+            depends: depends.iter().map(|&dep| dep.into()).collect(),
+            optdepends: optdepends.iter().map(|&dep| dep.into()).collect(),
+            provides: provides.iter().map(|&dep| dep.into()).collect(),
+            ..SyncPackage::default()
+        }
+    }
+
+    fn setup() -> (Packages, Installed) {
+        let packages = Packages::new(
+            ["app", "lib", "bash", "extra", "helper"]
+                .into_iter()
+                .collect::<FixedCompactStrings>(),
+            vec![
+                sync(
+                    "app",
+                    &["lib>=1.0", "sh", "helper"],
+                    &["extra: for extra features"],
+                    &[],
+                ),
+                sync("lib", &[], &[], &[]),
+                sync("bash", &[], &[], &["sh"]),
+                sync("extra", &["lib"], &[], &[]),
+            ],
+            vec![AurPackage {
+                name: "helper".into(),
+                version: "1.0-1".into(),
+                description: None,
+                maintainer: None,
+                num_votes: 0,
+            }],
+        );
+        let installed = Installed::new(
+            ["lib", "bash"]
+                .into_iter()
+                .map(|name| LocalPackage {
+                    name: name.into(),
+                    provides: (name == "bash").then(|| "sh".into()).into_iter().collect(),
+                    ..LocalPackage::default()
+                })
+                .collect(),
+            [(1, 0), (2, 1)].into_iter().collect(),
+            IntSet::default(),
+        );
+        (packages, installed)
+    }
+
+    fn labels(tree: &Tree) -> Vec<(usize, &str)> {
+        tree.rows()
+            .iter()
+            .map(|row| (row.depth, &*row.node.label))
+            .collect()
+    }
+
+    #[test]
+    fn dependencies() {
+        let (packages, installed) = setup();
+        let tree = Tree::new(Direction::Dependencies, 0, &packages, &installed);
+
+        assert_eq!(
+            labels(&tree),
+            [
+                (0, "app"),
+                (1, "lib>=1.0"),
+                (1, "sh"),
+                (1, "helper"),
+                (1, "extra")
+            ]
+        );
+        let children = tree.root.children.as_ref().unwrap();
+        // Provided dependencies lead to their provider.
+        assert_eq!(children[1].index, Some(2));
+        assert!(children[1].installed);
+        assert_eq!(children[3].kind, Kind::OptDepends);
+        assert!(!children[3].installed);
+    }
+
+    #[test]
+    fn expand_and_collapse() {
+        let (packages, installed) = setup();
+        let mut tree = Tree::new(Direction::Dependencies, 0, &packages, &installed);
+
+        tree.cursor = 4;
+        assert_eq!(tree.expand(&packages, &installed), None);
+        assert_eq!(tree.rows().len(), 6);
+        assert_eq!(labels(&tree)[5], (2, "lib"));
+
+        // Collapsing a collapsed node goes to its parent.
+        tree.move_down();
+        tree.collapse();
+        assert_eq!(tree.cursor, 4);
+        tree.collapse();
+        assert_eq!(tree.rows().len(), 5);
+        tree.cursor = 0;
+        tree.collapse();
+        assert_eq!(tree.rows().len(), 1);
+        tree.collapse();
+        assert_eq!(tree.cursor, 0);
+
+        // Children are only looked up once.
+        tree.expand(&packages, &installed);
+        tree.cursor = 4;
+        tree.expand(&packages, &installed);
+        assert_eq!(tree.rows().len(), 6);
+    }
+
+    #[test]
+    fn aur_children_are_fetched() {
+        let (packages, installed) = setup();
+        let mut tree = Tree::new(Direction::Dependencies, 0, &packages, &installed);
+
+        tree.cursor = 3;
+        assert_eq!(tree.expand(&packages, &installed), Some((vec![2], 4)));
+        assert!(tree.current().unwrap().children.is_none());
+
+        // Info for another package is ignored.
+        tree.set_children(&[2], 1, None, &packages, &installed);
+        assert!(tree.current().unwrap().children.is_none());
+        tree.set_children(&[2], 4, None, &packages, &installed);
+        assert!(!tree.current().unwrap().expandable());
+    }
+}
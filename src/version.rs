@@ -0,0 +1,289 @@
+use std::{cmp::Ordering, fmt};
+
+/// A package version, ordered the way pacman orders them.
+///
+/// Versions that only differ in ways pacman ignores, like `1.0` and `1.00` or `1.0-1` and `1.0`,
+/// are equal.
+#[derive(Clone, Copy, Debug)]
+pub struct Version<'a>(pub &'a str);
+
+impl PartialEq for Version<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version<'_> {}
+
+impl PartialOrd for Version<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        vercmp(self.0, other.0)
+    }
+}
+
+impl fmt::Display for Version<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Compares two package versions the way pacman's `vercmp` does.
+///
+/// Versions are made of an optional epoch, a version and an optional release, written as
+/// `epoch:version-release`. The release is only compared when both versions have one.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (epoch_a, version_a, release_a) = parse_evr(a);
+    let (epoch_b, version_b, release_b) = parse_evr(b);
+
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(version_a, version_b))
+        .then_with(|| match (release_a, release_b) {
+            (Some(release_a), Some(release_b)) => rpmvercmp(release_a, release_b),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Splits `evr` into its epoch, version and release.
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let digits = evr.bytes().take_while(u8::is_ascii_digit).count();
+
+    let (epoch, rest) = match evr[digits..].strip_prefix(':') {
+        Some(rest) if digits == 0 => ("0", rest),
+        Some(rest) => (&evr[..digits], rest),
+        None => ("0", evr),
+    };
+
+    match rest.rfind('-') {
+        Some(dash) => (epoch, &rest[..dash], Some(&rest[dash + 1..])),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Compares two version strings segment by segment, where segments are runs of digits or
+/// letters separated by anything else.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+
+    // The ends of the previous segments.
+    let (mut end_a, mut end_b) = (0, 0);
+    // The starts of the current segments.
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+
+        if one == a.len() || two == b.len() {
+            break;
+        }
+
+        // Longer separators win.
+        let (separator_a, separator_b) = (one - end_a, two - end_b);
+        if separator_a != separator_b {
+            return separator_a.cmp(&separator_b);
+        }
+
+        let is_num = a[one].is_ascii_digit();
+        let is_segment = |c: &u8| {
+            if is_num {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+
+        end_a = one + a[one..].iter().take_while(|c| is_segment(c)).count();
+        end_b = two + b[two..].iter().take_while(|c| is_segment(c)).count();
+
+        // Segments of different types, numeric segments are newer than alphabetic ones.
+        if two == end_b {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut segment_a = &a[one..end_a];
+        let mut segment_b = &b[two..end_b];
+
+        if is_num {
+            while let [b'0', rest @ ..] = segment_a {
+                segment_a = rest;
+            }
+            while let [b'0', rest @ ..] = segment_b {
+                segment_b = rest;
+            }
+
+            // Without leading zeros, the longer number is the larger one.
+            let ordering = segment_a.len().cmp(&segment_b.len());
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+
+        let ordering = segment_a.cmp(segment_b);
+        if ordering.is_ne() {
+            return ordering;
+        }
+
+        one = end_a;
+        two = end_b;
+    }
+
+    let rest_a = &a[one..];
+    let rest_b = &b[two..];
+
+    if rest_a.is_empty() && rest_b.is_empty() {
+        return Ordering::Equal;
+    }
+
+    // A remaining alphabetic segment never beats an empty string, as in `1.0alpha` < `1.0`,
+    // while anything else does, as in `1.0.1` > `1.0`.
+    if (rest_a.is_empty() && !rest_b[0].is_ascii_alphabetic())
+        || rest_a.first().is_some_and(u8::is_ascii_alphabetic)
+    {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The cases of pacman's `test/util/vercmptest.sh`, followed by a few more.
+    const CASES: &[(&str, &str, Ordering)] = {
+        use Ordering::*;
+
+        &[
+            // All similar length, no pkgrel.
+            ("1.5.0", "1.5.0", Equal),
+            ("1.5.1", "1.5.0", Greater),
+            // Mixed length.
+            ("1.5.1", "1.5", Greater),
+            // With pkgrel, simple.
+            ("1.5.0-1", "1.5.0-1", Equal),
+            ("1.5.0-1", "1.5.0-2", Less),
+            ("1.5.0-1", "1.5.1-1", Less),
+            ("1.5.0-2", "1.5.1-1", Less),
+            // With pkgrel, mixed lengths.
+            ("1.5-1", "1.5.1-1", Less),
+            ("1.5-2", "1.5.1-1", Less),
+            ("1.5-2", "1.5.1-2", Less),
+            // Mixed pkgrel inclusion.
+            ("1.5", "1.5-1", Equal),
+            ("1.5-1", "1.5", Equal),
+            ("1.1-1", "1.1", Equal),
+            ("1.0-1", "1.1", Less),
+            ("1.1-1", "1.0", Greater),
+            // Alphanumeric versions.
+            ("1.5b-1", "1.5-1", Less),
+            ("1.5b", "1.5", Less),
+            ("1.5b-1", "1.5", Less),
+            ("1.5b", "1.5.1", Less),
+            // From the manpage.
+            ("1.0a", "1.0alpha", Less),
+            ("1.0alpha", "1.0b", Less),
+            ("1.0b", "1.0beta", Less),
+            ("1.0beta", "1.0rc", Less),
+            ("1.0rc", "1.0", Less),
+            // Alpha-dotted versions.
+            ("1.5.a", "1.5", Greater),
+            ("1.5.b", "1.5.a", Greater),
+            ("1.5.1", "1.5.b", Greater),
+            // Alpha dots and dashes.
+            ("1.5.b-1", "1.5.b", Equal),
+            ("1.5-1", "1.5.b", Less),
+            // Same or similar content, differing separators.
+            ("2.0", "2_0", Equal),
+            ("2.0_a", "2_0.a", Equal),
+            ("2.0a", "2.0.a", Less),
+            ("2___a", "2_a", Greater),
+            // Epoch included version comparisons.
+            ("0:1.0", "0:1.0", Equal),
+            ("0:1.0", "0:1.1", Less),
+            ("1:1.0", "0:1.0", Greater),
+            ("1:1.0", "0:1.1", Greater),
+            ("1:1.0", "2:1.1", Less),
+            // Epoch and sometimes present pkgrel.
+            ("1:1.0", "0:1.0-1", Greater),
+            ("1:1.0-1", "0:1.1-1", Greater),
+            // Epoch included on one version.
+            ("0:1.0", "1.0", Equal),
+            ("0:1.0", "1.1", Less),
+            ("0:1.1", "1.0", Greater),
+            ("1:1.0", "1.0", Greater),
+            ("1:1.0", "1.1", Greater),
+            ("1:1.1", "1.1", Greater),
+            // Leading zeros.
+            ("1.05", "1.5", Equal),
+            ("1.0010", "1.10", Equal),
+            ("1.010", "1.9", Greater),
+            ("007", "8", Less),
+            // A trailing letter is older than nothing.
+            ("1.0a", "1.0", Less),
+            // Longer runs of separators are newer.
+            ("1..0", "1.0", Greater),
+            ("1.0", "1..0", Less),
+            ("1..0", "1..0", Equal),
+            // Numeric releases.
+            ("1.0-10", "1.0-9", Greater),
+            ("1.0-1.1", "1.0-1", Greater),
+        ]
+    };
+
+    #[test]
+    fn vercmp_cases() {
+        for &(a, b, expected) in CASES {
+            assert_eq!(vercmp(a, b), expected, "vercmp({a}, {b})");
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({b}, {a})");
+        }
+    }
+
+    #[test]
+    fn ord_agrees_with_vercmp() {
+        let versions = CASES
+            .iter()
+            .flat_map(|&(a, b, _)| [a, b])
+            .collect::<Vec<_>>();
+
+        for &a in &versions {
+            for &b in &versions {
+                let ordering = Version(a).cmp(&Version(b));
+                assert_eq!(ordering, vercmp(a, b), "{a} cmp {b}");
+                assert_eq!(
+                    ordering,
+                    Version(b).cmp(&Version(a)).reverse(),
+                    "{a} cmp {b}"
+                );
+                assert_eq!(Version(a) == Version(b), ordering.is_eq(), "{a} == {b}");
+                assert_eq!(
+                    Version(a).partial_cmp(&Version(b)),
+                    Some(ordering),
+                    "{a} partial_cmp {b}"
+                );
+            }
+        }
+    }
+}
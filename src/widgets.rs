@@ -2,6 +2,8 @@ use std::{borrow::Cow, marker::PhantomData};
 
 use tui::{buffer::Buffer, layout::*, style::*, text::*, widgets::*};
 
+use crate::mode::View;
+
 pub struct Title<'a> {
     _marker: PhantomData<Box<dyn Fn() + 'a>>,
 }
@@ -17,13 +19,14 @@ impl Title<'_> {
 pub struct TitleState<'a> {
     parui: Span<'a>,
     pub query: String,
+    pub descriptions: bool,
+    pub fuzzy: bool,
+    pub view: View,
     old_query: Cow<'a, str>,
     para_line: Vec<Span<'a>>,
     block: Block<'a>,
-    pub mod_: Modifier,
-    old_mod: Modifier,
-    pub col: Color,
-    old_col: Color,
+    pub style: Style,
+    old_style: Style,
     pub size: Rect,
     old_size: Rect,
 }
@@ -33,12 +36,13 @@ impl<'a> TitleState<'a> {
         Self {
             parui: Span::raw(" parui "),
             query: String::new(),
+            descriptions: false,
+            fuzzy: false,
+            view: View::Search,
             old_query: Cow::Borrowed(""),
             para_line: vec![Span::raw(" Search: "), Span::default()],
-            mod_: Modifier::default(),
-            old_mod: Modifier::default(),
-            col: Color::default(),
-            old_col: Color::default(),
+            style: Style::default(),
+            old_style: Style::default(),
             size: Rect::default(),
             old_size: Rect::default(),
             block: Block::default(),
@@ -50,7 +54,7 @@ impl<'a> StatefulWidget for Title<'a> {
     type State = TitleState<'a>;
 
     fn render(self, area: Rect, buf: &mut Buffer, s: &mut Self::State) {
-        let bold = Style::default().fg(s.col).add_modifier(s.mod_);
+        let style = s.style;
 
         if s.query != s.old_query || s.size.width != s.old_size.width {
             s.para_line[1] = Span::raw(
@@ -61,19 +65,29 @@ impl<'a> StatefulWidget for Title<'a> {
                     .collect::<String>(),
             );
         }
-        s.para_line[1].style = Style::default().fg(s.col);
+        s.para_line[1].style = style.remove_modifier(Modifier::BOLD);
 
-        s.para_line[0].style = bold;
-        s.parui.style = bold;
+        s.para_line[0].style = style;
+        let title = match (s.view, s.fuzzy, s.descriptions) {
+            (View::Upgrades, ..) => " parui (upgrades) ",
+            (View::Orphans, ..) => " parui (orphans) ",
+            (View::Search, false, false) => " parui ",
+            (View::Search, true, false) => " parui (fuzzy) ",
+            (View::Search, false, true) => " parui (descriptions) ",
+            (View::Search, true, true) => " parui (fuzzy, descriptions) ",
+        };
+        let retitled = s.parui.content != title;
+        s.parui.content = Cow::Borrowed(title);
+        s.parui.style = style;
         let para = Paragraph::new(Line::from(s.para_line.clone()));
 
-        if s.col != s.old_col || s.mod_ != s.old_mod {
+        if s.style != s.old_style || retitled {
             s.block = Block::default()
                 .title(s.parui.clone())
                 .title_alignment(Alignment::Center)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(s.col));
+                .border_style(style);
         }
 
         para.block(s.block.clone())